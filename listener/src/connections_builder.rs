@@ -133,6 +133,7 @@ pub mod tests {
         data_pushed_from_connections: Arc<Mutex<Vec<String>>>,
         result: Mutex<ResultsStoredInMock>,
         maybe_protocol_version: Mutex<Option<ProtocolVersion>>,
+        report_event_ids: bool,
     }
 
     impl Default for MockConnectionsBuilder {
@@ -141,6 +142,7 @@ pub mod tests {
                 data_pushed_from_connections: Arc::new(Mutex::new(vec![])),
                 result: Mutex::new(vec![Ok(HashMap::new())]),
                 maybe_protocol_version: Mutex::new(None),
+                report_event_ids: false,
             }
         }
     }
@@ -176,6 +178,17 @@ pub mod tests {
             Self::builder_based_on_result(rx, results)
         }
 
+        pub fn connected_then_dropped(times: usize) -> Self {
+            let (tx, rx) = channel(100);
+            let results = (0..times)
+                .map(|i| response_with_failing_events(&i.to_string(), &tx))
+                .collect();
+            Self {
+                report_event_ids: true,
+                ..Self::builder_based_on_result(rx, results)
+            }
+        }
+
         pub fn one_fails_immediatly() -> Self {
             let (tx, rx) = channel(100);
             let results = vec![response_with_failing_events("1", &tx)];
@@ -206,6 +219,7 @@ pub mod tests {
                 data_pushed_from_connections,
                 result: Mutex::new(results),
                 maybe_protocol_version: Mutex::new(None),
+                report_event_ids: false,
             }
         }
     }
@@ -252,7 +266,7 @@ pub mod tests {
         async fn build_connections(
             &self,
             _last_event_id_for_filter: Arc<Mutex<HashMap<Filter, u32>>>,
-            _last_seen_event_id_sender: FilterWithEventId,
+            last_seen_event_id_sender: FilterWithEventId,
             node_build_version: ProtocolVersion,
         ) -> Result<HashMap<Filter, Box<dyn ConnectionManager>>, Error> {
             let mut guard = self.maybe_protocol_version.lock().await;
@@ -260,7 +274,14 @@ pub mod tests {
             drop(guard);
             let mut guard = self.result.lock().await;
             if !guard.is_empty() {
-                return guard.remove(0);
+                let result = guard.remove(0);
+                if self.report_event_ids && result.is_ok() {
+                    last_seen_event_id_sender
+                        .send((Filter::Main, 1))
+                        .await
+                        .unwrap();
+                }
+                return result;
             }
             Err(Error::msg("No more connections to build"))
         }
@@ -23,13 +23,13 @@ pub(super) enum EventListenerStatus {
 impl EventListenerStatus {
     pub(super) fn log_status(&self, node_address: &str, sse_port: u16) {
         let status = match self {
-            EventListenerStatus::Preparing => 0,
-            EventListenerStatus::Connecting => 1,
-            EventListenerStatus::Connected => 2,
-            EventListenerStatus::Reconnecting => 3,
-            EventListenerStatus::Defunct => -1,
-            EventListenerStatus::IncompatibleVersion => -2,
-        } as f64;
+            EventListenerStatus::Preparing => 0.0,
+            EventListenerStatus::Connecting => 1.0,
+            EventListenerStatus::Connected => metrics::NODE_STATUS_CONNECTED,
+            EventListenerStatus::Reconnecting => 3.0,
+            EventListenerStatus::Defunct => -1.0,
+            EventListenerStatus::IncompatibleVersion => -2.0,
+        };
         let node_label = format!("{}:{}", node_address, sse_port);
         metrics::NODE_STATUSES
             .with_label_values(&[node_label.as_str()])
@@ -12,12 +12,22 @@ mod types;
 mod version_fetcher;
 use crate::event_listener_status::*;
 use anyhow::Error;
-use casper_event_types::Filter;
+use casper_event_types::{metrics, Filter};
 use casper_types::ProtocolVersion;
 use connection_manager::{ConnectionManager, ConnectionManagerError};
 use connection_tasks::ConnectionTasks;
 use connections_builder::{ConnectionsBuilder, DefaultConnectionsBuilder};
-use std::{collections::HashMap, net::IpAddr, str::FromStr, sync::Arc, time::Duration};
+use rand::Rng;
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 use tokio::{
     sync::{
         mpsc::{self, Sender},
@@ -36,6 +46,8 @@ pub struct EventListenerBuilder {
     pub node: NodeConnectionInterface,
     pub max_connection_attempts: usize,
     pub delay_between_attempts: Duration,
+    pub max_delay_between_attempts: Duration,
+    pub delay_jitter: Duration,
     pub allow_partial_connection: bool,
     pub sse_event_sender: Sender<SseEvent>,
     pub connection_timeout: Duration,
@@ -64,6 +76,8 @@ impl EventListenerBuilder {
             node: self.node.clone(),
             max_connection_attempts: self.max_connection_attempts,
             delay_between_attempts: self.delay_between_attempts,
+            max_delay_between_attempts: self.max_delay_between_attempts,
+            delay_jitter: self.delay_jitter,
             allow_partial_connection: self.allow_partial_connection,
             version_fetcher,
             connections_builder,
@@ -79,8 +93,15 @@ pub struct EventListener {
     node: NodeConnectionInterface,
     /// Maximum numbers the listener will retry connecting to the node.
     max_connection_attempts: usize,
-    /// Time the listener will wait between connection attempts
+    /// Time the listener will wait before the first reconnection attempt. Each consecutive failed
+    /// attempt doubles the wait, up to `max_delay_between_attempts`.
     delay_between_attempts: Duration,
+    /// Upper bound of the wait between connection attempts. If it's not greater than
+    /// `delay_between_attempts` the wait is constant.
+    max_delay_between_attempts: Duration,
+    /// Upper bound of a random delay added to every wait between connection attempts, so that
+    /// listeners of several sidecars don't reconnect to a node in lockstep.
+    delay_jitter: Duration,
     /// If set to false, the listener needs to connect to all endpoints a node should expose in a given `node_build_version` for the listener to start processing data.
     /// If set to true the listen will proceed after connecting to at least one connection.
     allow_partial_connection: bool,
@@ -109,13 +130,16 @@ impl EventListener {
     /// Spins up the connections and starts pushing data from node
     pub async fn stream_aggregated_events(&mut self) -> Result<(), Error> {
         log_status_for_event_listener(EventListenerStatus::Preparing, self);
-        let (last_event_id_for_filter, last_seen_event_id_sender) =
+        let (last_event_id_for_filter, last_seen_event_id_sender, connection_established) =
             self.start_last_event_id_registry(self.node.ip_address.to_string(), self.node.sse_port);
         log_status_for_event_listener(EventListenerStatus::Connecting, self);
         let mut current_attempt = 1;
+        // Drives the backoff; unlike `current_attempt` it starts over once a connection worked.
+        let mut consecutive_failures = 0;
         while current_attempt <= self.max_connection_attempts {
             if current_attempt > 1 {
-                sleep(self.delay_between_attempts).await;
+                count_reconnection_attempt(self);
+                sleep(self.delay_before_retry(consecutive_failures) + self.random_jitter()).await;
             }
             match self.get_version(current_attempt).await {
                 GetVersionResult::Ok(Some(protocol_version)) => {
@@ -124,6 +148,7 @@ impl EventListener {
                 }
                 GetVersionResult::Retry => {
                     current_attempt += 1;
+                    consecutive_failures += 1;
                     if current_attempt >= self.max_connection_attempts {
                         log_status_for_event_listener(EventListenerStatus::Defunct, self);
                     }
@@ -141,12 +166,39 @@ impl EventListener {
             {
                 warn_connection_lost(self, current_attempt);
             }
+            if connection_established.swap(false, Ordering::SeqCst) {
+                consecutive_failures = 0;
+            }
+            consecutive_failures += 1;
             current_attempt += 1;
         }
         log_status_for_event_listener(EventListenerStatus::Defunct, self);
         Err(Error::msg(MAX_CONNECTION_ATTEMPTS_REACHED))
     }
 
+    /// Exponential backoff: the delay doubles with every consecutive failure after the first one,
+    /// capped at `max_delay_between_attempts`.
+    fn delay_before_retry(&self, consecutive_failures: usize) -> Duration {
+        let exponent = consecutive_failures
+            .saturating_sub(1)
+            .min(u32::MAX as usize) as u32;
+        let delay = self
+            .delay_between_attempts
+            .checked_mul(2_u32.saturating_pow(exponent))
+            .unwrap_or(Duration::MAX);
+        delay.min(
+            self.max_delay_between_attempts
+                .max(self.delay_between_attempts),
+        )
+    }
+
+    fn random_jitter(&self) -> Duration {
+        if self.delay_jitter.is_zero() {
+            return Duration::ZERO;
+        }
+        rand::thread_rng().gen_range(Duration::ZERO..=self.delay_jitter)
+    }
+
     async fn do_connect(
         &mut self,
         last_event_id_for_filter: Arc<Mutex<HashMap<Filter, u32>>>,
@@ -264,21 +316,28 @@ impl EventListener {
         &self,
         node_address: String,
         sse_port: u16,
-    ) -> (CurrentFilterToIdHolder, FilterWithEventId) {
+    ) -> (CurrentFilterToIdHolder, FilterWithEventId, Arc<AtomicBool>) {
         let (last_seen_event_id_sender, mut last_seen_event_id_receiver) = mpsc::channel(10);
         let last_event_id_for_filter: CurrentFilterToIdHolder =
             Arc::new(Mutex::new(HashMap::<Filter, u32>::new()));
         let last_event_id_for_filter_for_thread = last_event_id_for_filter.clone();
+        let connection_established = Arc::new(AtomicBool::new(false));
+        let connection_established_for_thread = connection_established.clone();
         tokio::spawn(async move {
             while let Some((filter, id)) = last_seen_event_id_receiver.recv().await {
                 EventListenerStatus::Connected.log_status(node_address.as_str(), sse_port);
+                connection_established_for_thread.store(true, Ordering::SeqCst);
                 let last_event_id_for_filter_clone = last_event_id_for_filter_for_thread.clone();
                 let mut guard = last_event_id_for_filter_clone.lock().await;
                 guard.insert(filter, id);
                 drop(guard);
             }
         });
-        (last_event_id_for_filter, last_seen_event_id_sender)
+        (
+            last_event_id_for_filter,
+            last_seen_event_id_sender,
+            connection_established,
+        )
     }
 }
 
@@ -309,6 +368,16 @@ fn log_status_for_event_listener(status: EventListenerStatus, event_listener: &E
     status.log_status(node_address.as_str(), sse_port);
 }
 
+fn count_reconnection_attempt(event_listener: &EventListener) {
+    let node_label = format!(
+        "{}:{}",
+        event_listener.node.ip_address, event_listener.node.sse_port
+    );
+    metrics::NODE_RECONNECTION_ATTEMPTS
+        .with_label_values(&[node_label.as_str()])
+        .inc();
+}
+
 fn status_endpoint(ip_address: IpAddr, rest_port: u16) -> Result<Url, Error> {
     let status_endpoint_str = format!("http://{}:{}/status", ip_address, rest_port);
     Url::from_str(&status_endpoint_str).map_err(Error::from)
@@ -330,7 +399,12 @@ mod tests {
     };
     use anyhow::Error;
     use casper_types::ProtocolVersion;
-    use std::{collections::HashSet, str::FromStr, sync::Arc, time::Duration};
+    use std::{
+        collections::HashSet,
+        str::FromStr,
+        sync::Arc,
+        time::{Duration, Instant},
+    };
 
     #[tokio::test]
     async fn given_event_listener_should_not_connect_when_incompatible_version() {
@@ -421,6 +495,8 @@ mod tests {
             node: NodeConnectionInterface::default(),
             max_connection_attempts,
             delay_between_attempts: Duration::from_secs(1),
+            max_delay_between_attempts: Duration::from_secs(1),
+            delay_jitter: Duration::ZERO,
             allow_partial_connection,
             version_fetcher: Arc::new(version_fetcher),
             connections_builder,
@@ -428,6 +504,79 @@ mod tests {
         listener.stream_aggregated_events().await.unwrap_err()
     }
 
+    #[test]
+    fn should_back_off_exponentially_up_to_max_delay() {
+        let listener = EventListener {
+            node_build_version: ProtocolVersion::from_parts(1, 0, 0),
+            node: NodeConnectionInterface::default(),
+            max_connection_attempts: 10,
+            delay_between_attempts: Duration::from_secs(1),
+            max_delay_between_attempts: Duration::from_secs(5),
+            delay_jitter: Duration::ZERO,
+            allow_partial_connection: false,
+            version_fetcher: Arc::new(MockVersionFetcher::repeatable_from_protocol_version(
+                "1.5.10",
+            )),
+            connections_builder: Arc::new(MockConnectionsBuilder::ok_after_two_fails()),
+        };
+
+        let delays: Vec<u64> = (1..=5)
+            .map(|failures| listener.delay_before_retry(failures).as_secs())
+            .collect();
+
+        assert_eq!(delays, vec![1, 2, 4, 5, 5]);
+        assert_eq!(
+            listener.delay_before_retry(usize::MAX),
+            Duration::from_secs(5)
+        );
+        assert_eq!(listener.random_jitter(), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn should_reset_backoff_after_a_connection_was_established() {
+        let mut listener = EventListener {
+            node_build_version: ProtocolVersion::from_parts(1, 0, 0),
+            node: NodeConnectionInterface::default(),
+            max_connection_attempts: 5,
+            delay_between_attempts: Duration::from_millis(100),
+            max_delay_between_attempts: Duration::from_secs(10),
+            delay_jitter: Duration::ZERO,
+            allow_partial_connection: false,
+            version_fetcher: Arc::new(MockVersionFetcher::repeatable_from_protocol_version(
+                "1.5.10",
+            )),
+            connections_builder: Arc::new(MockConnectionsBuilder::connected_then_dropped(5)),
+        };
+        let start = Instant::now();
+
+        let err = listener.stream_aggregated_events().await.unwrap_err();
+
+        assert!(err.to_string().contains("Max connection attempts reached"));
+        // Four waits of 100ms each. Without the reset they would add up to 100+200+400+800ms.
+        assert!(start.elapsed() < Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn should_keep_jitter_within_configured_bound() {
+        let listener = EventListener {
+            node_build_version: ProtocolVersion::from_parts(1, 0, 0),
+            node: NodeConnectionInterface::default(),
+            max_connection_attempts: 10,
+            delay_between_attempts: Duration::from_secs(1),
+            max_delay_between_attempts: Duration::from_secs(1),
+            delay_jitter: Duration::from_millis(500),
+            allow_partial_connection: false,
+            version_fetcher: Arc::new(MockVersionFetcher::repeatable_from_protocol_version(
+                "1.5.10",
+            )),
+            connections_builder: Arc::new(MockConnectionsBuilder::default()),
+        };
+
+        for _ in 0..100 {
+            assert!(listener.random_jitter() <= Duration::from_millis(500));
+        }
+    }
+
     fn set_contains(set: HashSet<String>, value: Vec<&str>) -> bool {
         value.iter().all(|v| set.contains(*v))
     }
@@ -1,24 +1,43 @@
-use crate::types::config::AdminServerConfig;
-use crate::utils::{resolve_address, root_filter, Unexpected};
+use crate::types::config::{AdminServerConfig, MetricsAuthConfig};
+use crate::utils::{
+    count_in_flight, resolve_address, root_filter, RateLimitedIncoming, Unexpected,
+};
 use anyhow::Error;
-use casper_event_types::metrics::metrics_summary;
-use hyper::Server;
+use base64::{
+    alphabet,
+    engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+    Engine,
+};
+use casper_event_types::metrics::{connected_nodes_count, metrics_summary};
+use http::{header::WWW_AUTHENTICATE, Method, StatusCode};
+use hyper::{Body, Server};
 use std::net::TcpListener;
 use std::time::Duration;
+use subtle::ConstantTimeEq;
 use tower::{buffer::Buffer, make::Shared, ServiceBuilder};
 use warp::Filter;
-use warp::{Rejection, Reply};
+use warp::{reply::Response, Rejection, Reply};
 
 const BIND_ALL_INTERFACES: &str = "0.0.0.0";
+/// Basic auth credentials are accepted with or without the trailing base64 padding.
+const BASE64: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
 struct AdminServer {
     port: u16,
     max_concurrent_requests: u32,
     max_requests_per_second: u32,
+    max_connections_per_second: Option<u32>,
+    metrics_auth: Option<MetricsAuthConfig>,
 }
 
 impl AdminServer {
     pub async fn start(&self) -> Result<(), Error> {
-        let api = root_filter().or(metrics_filter());
+        let api = root_filter()
+            .or(metrics_filter(self.metrics_auth.clone()))
+            .or(readyz_filter(connected_nodes_count));
         let address = format!("{}:{}", BIND_ALL_INTERFACES, self.port);
         let socket_address = resolve_address(&address)?;
         let listener = TcpListener::bind(socket_address)?;
@@ -27,11 +46,16 @@ impl AdminServer {
         let tower_service = ServiceBuilder::new()
             .concurrency_limit(self.max_concurrent_requests as usize)
             .rate_limit(self.max_requests_per_second as u64, Duration::from_secs(1))
+            .map_future(|request| count_in_flight("admin", request))
             .service(warp_service);
 
-        Server::from_tcp(listener)?
-            .serve(Shared::new(Buffer::new(tower_service, 50)))
-            .await?;
+        Server::builder(RateLimitedIncoming::new(
+            listener,
+            self.max_connections_per_second,
+            "admin",
+        )?)
+        .serve(Shared::new(Buffer::new(tower_service, 50)))
+        .await?;
 
         Err(Error::msg("Admin server shutting down"))
     }
@@ -42,30 +66,133 @@ pub async fn run_server(config: AdminServerConfig) -> Result<(), Error> {
         port: config.port,
         max_concurrent_requests: config.max_concurrent_requests,
         max_requests_per_second: config.max_requests_per_second,
+        max_connections_per_second: config.max_connections_per_second,
+        metrics_auth: config.metrics_auth,
     }
     .start()
     .await
 }
 
 /// Return metrics data at a given time.
+/// If `metrics_auth` is configured, the request needs to carry matching credentials in the
+/// `Authorization` header, otherwise a 401 is returned.
 /// Return: prometheus-formatted metrics data.
 /// Example: curl http://127.0.0.1:18887/metrics
-fn metrics_filter() -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+fn metrics_filter(
+    metrics_auth: Option<MetricsAuthConfig>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
     warp::path!("metrics")
         .and(warp::get())
-        .and_then(metrics_handler)
+        .and(warp::header::optional::<String>("authorization"))
+        .and_then(move |authorization: Option<String>| {
+            metrics_handler(metrics_auth.clone(), authorization)
+        })
 }
 
-async fn metrics_handler() -> Result<impl Reply, Rejection> {
+async fn metrics_handler(
+    metrics_auth: Option<MetricsAuthConfig>,
+    authorization: Option<String>,
+) -> Result<Response, Rejection> {
+    if let Some(auth) = metrics_auth {
+        if !is_authorized(&auth, authorization.as_deref()) {
+            return Ok(build_401(&auth));
+        }
+    }
     let res_custom = metrics_summary()
         .map_err(|err| warp::reject::custom(Unexpected(Error::msg(err.to_string()))))?;
 
-    Ok(res_custom)
+    Ok(res_custom.into_response())
+}
+
+/// Checks the `Authorization` header value against the configured credentials.
+/// The auth scheme is matched case-insensitively, the credentials are compared in constant time
+/// so that they can't be guessed by timing the responses.
+fn is_authorized(auth: &MetricsAuthConfig, authorization: Option<&str>) -> bool {
+    let (scheme, credentials) = match authorization.and_then(|value| value.trim().split_once(' ')) {
+        Some((scheme, credentials)) => (scheme, credentials.trim_start()),
+        None => return false,
+    };
+    match auth {
+        MetricsAuthConfig::BearerToken { bearer_token } => {
+            scheme.eq_ignore_ascii_case("Bearer")
+                && bool::from(credentials.as_bytes().ct_eq(bearer_token.as_bytes()))
+        }
+        MetricsAuthConfig::BasicAuth { username, password } => {
+            scheme.eq_ignore_ascii_case("Basic")
+                && basic_credentials_match(credentials, username, password)
+        }
+    }
+}
+
+/// Decodes base64 encoded `username:password` credentials, splitting them on the first `:`.
+/// Both parts are always compared, so the timing doesn't tell which of them was wrong.
+fn basic_credentials_match(credentials: &str, username: &str, password: &str) -> bool {
+    let decoded = match BASE64.decode(credentials) {
+        Ok(decoded) => decoded,
+        Err(_) => return false,
+    };
+    let (given_username, given_password) = match decoded.iter().position(|byte| *byte == b':') {
+        Some(index) => (&decoded[..index], &decoded[index + 1..]),
+        None => return false,
+    };
+    bool::from(
+        given_username.ct_eq(username.as_bytes()) & given_password.ct_eq(password.as_bytes()),
+    )
+}
+
+/// Creates a 401 response (Unauthorized) to be returned if the metrics scrape didn't provide
+/// valid credentials.
+fn build_401(auth: &MetricsAuthConfig) -> Response {
+    let challenge = match auth {
+        MetricsAuthConfig::BearerToken { .. } => "Bearer",
+        MetricsAuthConfig::BasicAuth { .. } => "Basic realm=\"metrics\"",
+    };
+    warp::reply::with_header(
+        warp::reply::with_status("Unauthorized", StatusCode::UNAUTHORIZED),
+        WWW_AUTHENTICATE,
+        challenge,
+    )
+    .into_response()
+}
+
+/// Return readiness of the sidecar, meant for readiness probes.
+/// Return: 200 if the sidecar is connected to at least one node, 503 otherwise. HEAD requests get
+/// the same status without a body.
+/// Example: curl http://127.0.0.1:18887/readyz
+fn readyz_filter<F>(
+    connected_nodes: F,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone
+where
+    F: Fn() -> usize + Clone + Send + Sync + 'static,
+{
+    warp::path!("readyz")
+        .and(warp::get().or(warp::head()).unify())
+        .and(warp::method())
+        .map(move |method: Method| {
+            let mut response = build_readyz_response(connected_nodes());
+            if method == Method::HEAD {
+                *response.body_mut() = Body::empty();
+            }
+            response
+        })
+}
+
+fn build_readyz_response(connected_nodes: usize) -> Response {
+    if connected_nodes > 0 {
+        warp::reply::with_status("Ready", StatusCode::OK).into_response()
+    } else {
+        warp::reply::with_status("Not connected to any node", StatusCode::SERVICE_UNAVAILABLE)
+            .into_response()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{admin_server::run_server, types::config::AdminServerConfig};
+    use crate::{
+        admin_server::{build_readyz_response, is_authorized, readyz_filter, run_server},
+        types::config::{AdminServerConfig, MetricsAuthConfig},
+    };
+    use http::StatusCode;
     use portpicker::pick_unused_port;
     use reqwest::Response;
 
@@ -77,17 +204,177 @@ mod tests {
             port,
             max_concurrent_requests: 1,
             max_requests_per_second: 1,
+            max_connections_per_second: None,
+            metrics_auth: None,
+        };
+        tokio::spawn(run_server(admin_config));
+
+        let response = fetch_metrics_data(&request_url, None).await;
+        let text = response.text().await.unwrap();
+        assert!(text.contains("process_cpu_seconds_total"));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn given_bearer_token_should_reject_unauthenticated_scrapes() {
+        let port = pick_unused_port().unwrap();
+        let request_url = format!("http://localhost:{}/metrics", port);
+        let admin_config = AdminServerConfig {
+            port,
+            max_concurrent_requests: 10,
+            max_requests_per_second: 10,
+            max_connections_per_second: None,
+            metrics_auth: Some(MetricsAuthConfig::BearerToken {
+                bearer_token: "secret".to_string(),
+            }),
         };
         tokio::spawn(run_server(admin_config));
 
-        let response = fetch_metrics_data(&request_url).await;
+        let response = fetch_metrics_data(&request_url, None).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = fetch_metrics_data(&request_url, Some("Bearer wrong")).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = fetch_metrics_data(&request_url, Some("Bearer secret")).await;
+        assert_eq!(response.status(), StatusCode::OK);
         let text = response.text().await.unwrap();
         assert!(text.contains("process_cpu_seconds_total"));
     }
 
-    async fn fetch_metrics_data(request_url: &String) -> Response {
-        reqwest::Client::new()
-            .get(request_url)
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn given_basic_auth_should_reject_unauthenticated_scrapes() {
+        let port = pick_unused_port().unwrap();
+        let request_url = format!("http://localhost:{}/metrics", port);
+        let admin_config = AdminServerConfig {
+            port,
+            max_concurrent_requests: 10,
+            max_requests_per_second: 10,
+            max_connections_per_second: None,
+            metrics_auth: Some(MetricsAuthConfig::BasicAuth {
+                username: "prometheus".to_string(),
+                password: "p@$$w0rd".to_string(),
+            }),
+        };
+        tokio::spawn(run_server(admin_config));
+
+        let response = fetch_metrics_data(&request_url, None).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = reqwest::Client::new()
+            .get(&request_url)
+            .basic_auth("prometheus", Some("p@$$w0rd"))
+            .send()
+            .await
+            .expect("Error requesting the /metrics endpoint");
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn given_connection_rate_limit_should_close_excess_connections() {
+        let port = pick_unused_port().unwrap();
+        let request_url = format!("http://localhost:{}/metrics", port);
+        let admin_config = AdminServerConfig {
+            port,
+            max_concurrent_requests: 10,
+            max_requests_per_second: 10,
+            max_connections_per_second: Some(1),
+            metrics_auth: None,
+        };
+        tokio::spawn(run_server(admin_config));
+
+        let response = fetch_metrics_data(&request_url, None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // A new client opens a new connection, which exceeds the limit.
+        let result = reqwest::Client::new().get(&request_url).send().await;
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn should_match_auth_scheme_case_insensitively() {
+        let bearer = MetricsAuthConfig::BearerToken {
+            bearer_token: "secret".to_string(),
+        };
+        assert!(is_authorized(&bearer, Some("Bearer secret")));
+        assert!(is_authorized(&bearer, Some("bearer secret")));
+        assert!(is_authorized(&bearer, Some("BEARER  secret")));
+        assert!(!is_authorized(&bearer, Some("bearer Secret")));
+        assert!(!is_authorized(&bearer, Some("Basic secret")));
+        assert!(!is_authorized(&bearer, Some("secret")));
+        assert!(!is_authorized(&bearer, None));
+
+        let basic = MetricsAuthConfig::BasicAuth {
+            username: "prometheus".to_string(),
+            password: "p@$$w0rd".to_string(),
+        };
+        assert!(is_authorized(
+            &basic,
+            Some("basic cHJvbWV0aGV1czpwQCQkdzByZA==")
+        ));
+        assert!(is_authorized(
+            &basic,
+            Some("Basic cHJvbWV0aGV1czpwQCQkdzByZA")
+        ));
+        assert!(!is_authorized(&basic, Some("basic cHJvbWV0aGV1czo=")));
+        assert!(!is_authorized(&basic, Some("Basic cHJvbWV0aGV1cw==")));
+        assert!(!is_authorized(&basic, Some("Basic not base64!")));
+
+        let password_with_colon = MetricsAuthConfig::BasicAuth {
+            username: "prometheus".to_string(),
+            password: "pass:word".to_string(),
+        };
+        // "prometheus:pass:word"
+        assert!(is_authorized(
+            &password_with_colon,
+            Some("Basic cHJvbWV0aGV1czpwYXNzOndvcmQ=")
+        ));
+        // "prometheus:pass"
+        assert!(!is_authorized(
+            &password_with_colon,
+            Some("Basic cHJvbWV0aGV1czpwYXNz")
+        ));
+    }
+
+    #[test]
+    fn readyz_should_depend_on_node_connections() {
+        assert_eq!(
+            build_readyz_response(0).status(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert_eq!(build_readyz_response(1).status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn readyz_should_answer_head_requests() {
+        let response = warp::test::request()
+            .method("HEAD")
+            .path("/readyz")
+            .reply(&readyz_filter(|| 1))
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.body().is_empty());
+
+        let response = warp::test::request()
+            .method("HEAD")
+            .path("/readyz")
+            .reply(&readyz_filter(|| 0))
+            .await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(response.body().is_empty());
+
+        let response = warp::test::request()
+            .path("/readyz")
+            .reply(&readyz_filter(|| 1))
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.body(), "Ready");
+    }
+
+    async fn fetch_metrics_data(request_url: &String, authorization: Option<&str>) -> Response {
+        let mut request = reqwest::Client::new().get(request_url);
+        if let Some(authorization) = authorization {
+            request = request.header("authorization", authorization);
+        }
+        request
             .send()
             .await
             .expect("Error requesting the /metrics endpoint")
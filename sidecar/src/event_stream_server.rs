@@ -27,16 +27,27 @@ mod http_server;
 mod sse_server;
 #[cfg(test)]
 mod tests;
-use crate::utils::{resolve_address, ListeningError};
-use casper_event_types::{sse_data::SseData, Filter as SseFilter};
+use crate::utils::{
+    cors_with_allowed_origins, hide_cors_headers_from_disallowed_origins, resolve_address,
+    ListeningError, RateLimitedIncoming,
+};
+use casper_event_types::{metrics, sse_data::SseData, Filter as SseFilter};
 pub use config::Config;
 use event_indexer::{EventIndex, EventIndexer};
+use futures::FutureExt;
+use hyper::Server;
+use prometheus::IntGaugeVec;
 use sse_server::ChannelsAndFilter;
-use std::{fmt::Debug, net::SocketAddr, path::PathBuf};
+use std::{
+    fmt::Debug,
+    net::{SocketAddr, TcpListener},
+    path::PathBuf,
+};
 use tokio::sync::{
     mpsc::{self, UnboundedSender},
     oneshot,
 };
+use tower::make::Shared;
 use tracing::{info, warn};
 use warp::Filter;
 
@@ -69,29 +80,58 @@ pub(crate) struct EventStreamServer {
 
 impl EventStreamServer {
     pub(crate) fn new(config: Config, storage_path: PathBuf) -> Result<Self, ListeningError> {
+        Self::start(config, storage_path, metrics::SSE_SUBSCRIBERS.clone())
+    }
+
+    fn start(
+        config: Config,
+        storage_path: PathBuf,
+        subscribers_gauge: IntGaugeVec,
+    ) -> Result<Self, ListeningError> {
         let required_address = resolve_address_and_retype(&config.address)?;
         let event_indexer = EventIndexer::new(storage_path);
         let (sse_data_sender, sse_data_receiver) = mpsc::unbounded_channel();
 
         // Event stream channels and filter.
         let ChannelsAndFilter {
-            event_broadcaster,
+            state,
             new_subscriber_info_receiver,
             sse_filter,
         } = ChannelsAndFilter::new(
             get_broadcast_channel_size(&config),
             config.max_concurrent_subscribers,
+            subscribers_gauge,
         );
+        let cors = cors_with_allowed_origins(config.cors_allowed_origins.as_deref())
+            .map_err(ListeningError::Cors)?;
         let (shutdown_sender, shutdown_receiver) = oneshot::channel::<()>();
-        let (listening_address, server_with_shutdown) =
-            warp::serve(sse_filter.with(warp::cors().allow_any_origin()))
-                .try_bind_with_graceful_shutdown(required_address, async {
-                    shutdown_receiver.await.ok();
-                })
-                .map_err(|error| ListeningError::Listen {
-                    address: required_address,
-                    error: Box::new(error),
-                })?;
+        let listen_error =
+            |error: Box<dyn std::error::Error + Send + Sync>| ListeningError::Listen {
+                address: required_address,
+                error,
+            };
+        let listener =
+            TcpListener::bind(required_address).map_err(|error| listen_error(Box::new(error)))?;
+        let listening_address = listener
+            .local_addr()
+            .map_err(|error| listen_error(Box::new(error)))?;
+        let incoming = RateLimitedIncoming::new(listener, config.max_connections_per_second, "sse")
+            .map_err(|error| listen_error(error.into()))?;
+        let server_with_shutdown = Server::builder(incoming)
+            .serve(Shared::new(warp::service(
+                hide_cors_headers_from_disallowed_origins(
+                    sse_filter.with(cors),
+                    config.cors_allowed_origins.clone(),
+                ),
+            )))
+            .with_graceful_shutdown(async {
+                shutdown_receiver.await.ok();
+            })
+            .map(|result| {
+                if let Err(error) = result {
+                    warn!(%error, "event stream server failed");
+                }
+            });
         info!(address=%listening_address, "started event stream server");
 
         tokio::spawn(http_server::run(
@@ -99,8 +139,8 @@ impl EventStreamServer {
             server_with_shutdown,
             shutdown_sender,
             sse_data_receiver,
-            event_broadcaster,
             new_subscriber_info_receiver,
+            state,
         ));
         Ok(EventStreamServer {
             sse_data_sender,
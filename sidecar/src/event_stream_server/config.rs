@@ -24,11 +24,23 @@ pub struct Config {
 
     /// Default maximum number of subscribers across all event streams permitted at any one time.
     pub max_concurrent_subscribers: u32,
+
+    /// Origins allowed to make cross-origin requests. Any origin is allowed if not set.
+    pub cors_allowed_origins: Option<Vec<String>>,
+
+    /// Maximum number of new connections accepted per second. Unlimited if not set.
+    pub max_connections_per_second: Option<u32>,
 }
 
 impl Config {
     /// Creates an instance for `EventStreamServer`.
-    pub fn new(port: u16, buffer_length: Option<u32>, max_subscribers: Option<u32>) -> Self {
+    pub fn new(
+        port: u16,
+        buffer_length: Option<u32>,
+        max_subscribers: Option<u32>,
+        cors_allowed_origins: Option<Vec<String>>,
+        max_connections_per_second: Option<u32>,
+    ) -> Self {
         let address = format!("{}:{}", DEFAULT_ADDRESS, port);
 
         Config {
@@ -36,12 +48,14 @@ impl Config {
             event_stream_buffer_length: buffer_length.unwrap_or(DEFAULT_EVENT_STREAM_BUFFER_LENGTH),
             max_concurrent_subscribers: max_subscribers
                 .unwrap_or(DEFAULT_MAX_CONCURRENT_SUBSCRIBERS),
+            cors_allowed_origins,
+            max_connections_per_second,
         }
     }
 }
 
 impl Default for Config {
     fn default() -> Self {
-        Config::new(0, None, None)
+        Config::new(0, None, None, None, None)
     }
 }
@@ -3,7 +3,10 @@ use std::str::FromStr;
 use super::{
     config::Config,
     event_indexer::EventIndex,
-    sse_server::{BroadcastChannelMessage, Id, NewSubscriberInfo, ServerSentEvent},
+    sse_server::{
+        BroadcastChannelMessage, BufferedIdRange, Id, NewSubscriberInfo, ServerSentEvent,
+        SseServerState,
+    },
 };
 use casper_event_types::{sse_data::SseData, Filter};
 use casper_types::ProtocolVersion;
@@ -12,7 +15,6 @@ use once_cell::sync::Lazy;
 use tokio::{
     select,
     sync::{
-        broadcast,
         mpsc::{self, error::SendError},
         oneshot,
     },
@@ -35,18 +37,20 @@ pub static SIDECAR_VERSION: Lazy<ProtocolVersion> = Lazy::new(|| {
 /// * `server_shutdown_sender` is the channel by which the server will be notified to shut down.
 /// * `data_receiver` will provide the server with local events which should then be sent to all
 ///   subscribed clients.
-/// * `broadcaster` is used by the server to send events to each subscribed client after receiving
-///   them via the `data_receiver`.
 /// * `new_subscriber_info_receiver` is used to notify the server of the details of a new client
 ///   having subscribed to the event stream.  It allows the server to populate that client's stream
 ///   with the requested number of historical events.
+/// * `state` holds the broadcaster used by the server to send events to each subscribed client
+///   after receiving them via the `data_receiver`, and the range of buffered event IDs which the
+///   server keeps up to date, so that clients resuming via `Last-Event-ID` can be told if they're
+///   too far behind.
 pub(super) async fn run(
     config: Config,
     server_with_shutdown: impl Future<Output = ()> + Send + 'static,
     server_shutdown_sender: oneshot::Sender<()>,
     mut data_receiver: OutboundReceiver,
-    broadcaster: broadcast::Sender<BroadcastChannelMessage>,
     mut new_subscriber_info_receiver: mpsc::UnboundedReceiver<NewSubscriberInfo>,
+    state: SseServerState,
 ) {
     let server_joiner = task::spawn(server_with_shutdown);
     let mut buffer = build_buffer(config);
@@ -63,7 +67,7 @@ pub(super) async fn run(
                     }
                 }
                 maybe_data = data_receiver.recv() => {
-                    if handle_incoming_data(maybe_data, &mut latest_protocol_version, &mut buffer, &broadcaster).await.is_err() {
+                    if handle_incoming_data(maybe_data, &mut latest_protocol_version, &mut buffer, &state).await.is_err() {
                         break;
                     }
                 }
@@ -74,7 +78,7 @@ pub(super) async fn run(
     // paired with `data_receiver` is dropped.  `server_joiner` will never return here.
     let _ = future::select(server_joiner, event_stream_fut.boxed()).await;
     // Kill the event-stream handlers, and shut down the server.
-    let _ = broadcaster.send(BroadcastChannelMessage::Shutdown);
+    let _ = state.broadcaster.send(BroadcastChannelMessage::Shutdown);
     let _ = server_shutdown_sender.send(());
 }
 
@@ -115,7 +119,7 @@ async fn handle_incoming_data(
         Vec<(ProtocolVersion, ServerSentEvent)>,
         (ProtocolVersion, ServerSentEvent),
     >,
-    broadcaster: &broadcast::Sender<BroadcastChannelMessage>,
+    state: &SseServerState,
 ) -> Result<(), ()> {
     match maybe_data {
         Some((maybe_event_index, data, inbound_filter, maybe_json_data)) => {
@@ -135,13 +139,18 @@ async fn handle_incoming_data(
                     }
                     Some(v) => {
                         buffer.push((*v, event.clone()));
+                        update_buffered_id_range(
+                            buffer,
+                            maybe_event_index,
+                            &state.buffered_id_range,
+                        );
                     }
                 },
             };
             let message = BroadcastChannelMessage::ServerSentEvent(event);
             // This can validly fail if there are no connected clients, so don't log
             // the error.
-            let _ = broadcaster.send(message);
+            let _ = state.broadcaster.send(message);
             Ok(())
         }
         None => {
@@ -152,6 +161,19 @@ async fn handle_incoming_data(
     }
 }
 
+/// `newest` is the id of the event just pushed to `buffer`. It's passed in because finding the
+/// last buffered event would mean walking the whole buffer.
+fn update_buffered_id_range(
+    buffer: &WheelBuf<Vec<(ProtocolVersion, ServerSentEvent)>, (ProtocolVersion, ServerSentEvent)>,
+    newest: Option<Id>,
+    buffered_id_range: &BufferedIdRange,
+) {
+    let oldest = buffer.iter().next().and_then(|tuple| tuple.1.id);
+    if let (Some(oldest), Some(newest)) = (oldest, newest) {
+        *buffered_id_range.write().unwrap() = Some((oldest, newest));
+    }
+}
+
 async fn register_new_subscriber(
     subscriber: NewSubscriberInfo,
     buffer: &WheelBuf<Vec<(ProtocolVersion, ServerSentEvent)>, (ProtocolVersion, ServerSentEvent)>,
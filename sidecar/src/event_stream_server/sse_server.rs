@@ -3,11 +3,13 @@
 use super::endpoint::Endpoint;
 #[cfg(feature = "additional-metrics")]
 use crate::utils::start_metrics_thread;
+use crate::utils::DecrementOnDrop;
 use casper_event_types::{sse_data::EventFilter, sse_data::SseData, Deploy, Filter as SseFilter};
 use casper_types::ProtocolVersion;
 use futures::{future, Stream, StreamExt};
-use http::StatusCode;
+use http::{header::RETRY_AFTER, HeaderValue, StatusCode};
 use hyper::Body;
+use prometheus::IntGaugeVec;
 use serde::Serialize;
 use serde_json::Value;
 use std::{
@@ -46,6 +48,11 @@ pub const SSE_API_SIGNATURES_PATH: &str = "sigs";
 pub const SSE_API_SIDECAR_PATH: &str = "sidecar";
 /// The URL query string field name.
 pub const QUERY_FIELD: &str = "start_from";
+/// The header with which a reconnecting client supplies the ID of the last event it received.
+pub const LAST_EVENT_ID_HEADER: &str = "last-event-id";
+/// The number of seconds a client rejected for exceeding the subscriber limit is advised to wait
+/// before retrying, sent in the `Retry-After` header.
+const SUBSCRIBER_LIMIT_RETRY_AFTER_SECONDS: u32 = 10;
 
 /// The filter associated with `/events` path.
 const EVENTS_FILTER: [EventFilter; 5] = [
@@ -75,6 +82,21 @@ const SIDECAR_FILTER: [EventFilter; 1] = [EventFilter::SidecarVersion];
 /// The "id" field of the events sent on the event stream to clients.
 pub type Id = u32;
 type UrlProps = (&'static [EventFilter], &'static Endpoint, Option<u32>);
+/// The IDs of the oldest and newest events held in the server's event buffer, or `None` if no
+/// events have been buffered yet.
+pub(super) type BufferedIdRange = Arc<RwLock<Option<(Id, Id)>>>;
+
+/// State shared between the event stream server's warp filter and the task feeding it with events.
+#[derive(Clone)]
+pub(super) struct SseServerState {
+    /// Sends events to the streams of all subscribed clients.
+    pub(super) broadcaster: broadcast::Sender<BroadcastChannelMessage>,
+    /// Kept up to date by the server and used to tell whether a client resuming via
+    /// `Last-Event-ID` can still be served from the event buffer.
+    pub(super) buffered_id_range: BufferedIdRange,
+    /// The `sse_subscribers` gauge the subscriber counts are reported to.
+    pub(super) subscribers_gauge: IntGaugeVec,
+}
 
 #[derive(Serialize)]
 #[serde(rename_all = "PascalCase")]
@@ -315,6 +337,39 @@ fn parse_query(query: HashMap<String, String>) -> Result<Option<Id>, Response> {
     }
 }
 
+/// Extracts the starting event ID from the `Last-Event-ID` header sent by a reconnecting client,
+/// or `None` if the header is absent or empty.
+///
+/// The client resumes from the event following the one it last received.  Returns a 400 response
+/// if the header doesn't hold an event ID, or a 410 response if the event following it has already
+/// been purged from the buffer, in which case the client has to resubscribe from scratch.
+fn parse_last_event_id(
+    maybe_last_event_id: Option<String>,
+    buffered_id_range: &BufferedIdRange,
+) -> Result<Option<Id>, Response> {
+    let last_event_id = match maybe_last_event_id.as_deref().map(str::trim) {
+        None | Some("") => return Ok(None),
+        Some(id_str) => id_str.parse::<Id>().map_err(|_| create_400())?,
+    };
+    let start_from = last_event_id.wrapping_add(1);
+    if is_too_far_behind(start_from, *buffered_id_range.read().unwrap()) {
+        info!(%last_event_id, "client is too far behind the event buffer: rejecting it");
+        return Err(create_410());
+    }
+    Ok(Some(start_from))
+}
+
+/// Returns `true` if the events from `start_from` onwards can't all be served from a buffer
+/// holding the events in `buffered_id_range`, taking the wrapping of IDs into account.
+fn is_too_far_behind(start_from: Id, buffered_id_range: Option<(Id, Id)>) -> bool {
+    match buffered_id_range {
+        Some((oldest, newest)) => {
+            start_from.wrapping_sub(oldest) > newest.wrapping_sub(oldest).wrapping_add(1)
+        }
+        None => false,
+    }
+}
+
 /// Creates a 404 response with a useful error message in the body.
 fn create_404() -> Response {
     let mut response = Response::new(Body::from(format!(
@@ -339,16 +394,40 @@ fn create_422() -> Response {
     response
 }
 
+/// Creates a 400 response with a useful error message in the body for use in case of a bad
+/// `Last-Event-ID` header.
+fn create_400() -> Response {
+    let mut response = Response::new(Body::from(
+        "invalid header: expected 'Last-Event-ID: <EVENT ID>'\n",
+    ));
+    *response.status_mut() = StatusCode::BAD_REQUEST;
+    response
+}
+
+/// Creates a 410 response (Gone) to be returned if the events following the client's
+/// `Last-Event-ID` are no longer buffered.
+fn create_410() -> Response {
+    let mut response = Response::new(Body::from(
+        "last event ID is too far behind: resubscribe without 'Last-Event-ID'\n",
+    ));
+    *response.status_mut() = StatusCode::GONE;
+    response
+}
+
 /// Creates a 503 response (Service Unavailable) to be returned if the server has too many
-/// subscribers.
+/// subscribers.  The response carries a `Retry-After` header advising the client when to retry.
 fn create_503() -> Response {
     let mut response = Response::new(Body::from("server has reached limit of subscribers"));
     *response.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+    let _ = response.headers_mut().insert(
+        RETRY_AFTER,
+        HeaderValue::from(SUBSCRIBER_LIMIT_RETRY_AFTER_SECONDS),
+    );
     response
 }
 
 pub(super) struct ChannelsAndFilter {
-    pub(super) event_broadcaster: broadcast::Sender<BroadcastChannelMessage>,
+    pub(super) state: SseServerState,
     pub(super) new_subscriber_info_receiver: mpsc::UnboundedReceiver<NewSubscriberInfo>,
     pub(super) sse_filter: BoxedFilter<(Response,)>,
 }
@@ -356,15 +435,21 @@ pub(super) struct ChannelsAndFilter {
 fn serve_sse_response_handler(
     maybe_path_param: Option<String>,
     query: HashMap<String, String>,
-    cloned_broadcaster: tokio::sync::broadcast::Sender<BroadcastChannelMessage>,
+    maybe_last_event_id: Option<String>,
+    state: &SseServerState,
     max_concurrent_subscribers: u32,
     new_subscriber_info_sender: UnboundedSender<NewSubscriberInfo>,
     #[cfg(feature = "additional-metrics")] metrics_sender: Sender<()>,
 ) -> http::Response<Body> {
-    if let Some(value) = validate(&cloned_broadcaster, max_concurrent_subscribers) {
+    if let Some(value) = validate(&state.broadcaster, max_concurrent_subscribers) {
         return value;
     }
-    let (event_filter, stream_filter, start_from) = match parse_url_props(maybe_path_param, query) {
+    let (event_filter, stream_filter, start_from) = match parse_url_props(
+        maybe_path_param,
+        query,
+        maybe_last_event_id,
+        &state.buffered_id_range,
+    ) {
         Ok(value) => value,
         Err(error_response) => return error_response,
     };
@@ -386,22 +471,32 @@ fn serve_sse_response_handler(
     }
 
     // Create a channel for the client's handler to receive the stream of ongoing events.
-    let ongoing_events_receiver = cloned_broadcaster.subscribe();
+    let ongoing_events_receiver = state.broadcaster.subscribe();
+    // The client counts as a subscriber until its stream is dropped, i.e. until it disconnects or
+    // the stream ends.
+    let subscriber =
+        DecrementOnDrop::increment(state.subscribers_gauge.with_label_values(&["current"]));
 
-    sse::reply(sse::keep_alive().stream(stream_to_client(
+    let stream = stream_to_client(
         initial_events_receiver,
         ongoing_events_receiver,
         stream_filter,
         event_filter,
         #[cfg(feature = "additional-metrics")]
         metrics_sender,
-    )))
-    .into_response()
+    )
+    .map(move |event| {
+        let _subscriber = &subscriber;
+        event
+    });
+    sse::reply(sse::keep_alive().stream(stream)).into_response()
 }
 
 fn parse_url_props(
     maybe_path_param: Option<String>,
     query: HashMap<String, String>,
+    maybe_last_event_id: Option<String>,
+    buffered_id_range: &BufferedIdRange,
 ) -> Result<UrlProps, http::Response<Body>> {
     let path_param = maybe_path_param.unwrap_or_else(|| SSE_API_ROOT_PATH.to_string());
     let event_filter = match get_filter(path_param.as_str()) {
@@ -416,6 +511,11 @@ fn parse_url_props(
         Ok(maybe_id) => maybe_id,
         Err(error_response) => return Err(error_response),
     };
+    // An explicit `start_from` query takes precedence over the `Last-Event-ID` header.
+    let start_from = match start_from {
+        Some(id) => Some(id),
+        None => parse_last_event_id(maybe_last_event_id, buffered_id_range)?,
+    };
     Ok((event_filter, stream_filter, start_from))
 }
 
@@ -437,10 +537,25 @@ fn validate(
 impl ChannelsAndFilter {
     /// Creates the message-passing channels required to run the event-stream server and the warp
     /// filter for the event-stream server.
-    pub(super) fn new(broadcast_channel_size: usize, max_concurrent_subscribers: u32) -> Self {
+    ///
+    /// `subscribers_gauge` is the `sse_subscribers` gauge the subscriber counts are reported to.
+    pub(super) fn new(
+        broadcast_channel_size: usize,
+        max_concurrent_subscribers: u32,
+        subscribers_gauge: IntGaugeVec,
+    ) -> Self {
         // Create a channel to broadcast new events to all subscribed clients' streams.
-        let (event_broadcaster, _) = broadcast::channel(broadcast_channel_size);
-        let cloned_broadcaster = event_broadcaster.clone();
+        let (broadcaster, _) = broadcast::channel(broadcast_channel_size);
+        subscribers_gauge
+            .with_label_values(&["max"])
+            .set(max_concurrent_subscribers as i64);
+        subscribers_gauge.with_label_values(&["current"]).set(0);
+        let state = SseServerState {
+            broadcaster,
+            buffered_id_range: BufferedIdRange::default(),
+            subscribers_gauge,
+        };
+        let cloned_state = state.clone();
 
         #[cfg(feature = "additional-metrics")]
         let tx = start_metrics_thread("pushing outbound_events".to_string());
@@ -455,13 +570,17 @@ impl ChannelsAndFilter {
             .and(opt)
             .and(path::end())
             .and(warp::query())
+            .and(warp::header::optional::<String>(LAST_EVENT_ID_HEADER))
             .map(
-                move |maybe_path_param: Option<String>, query: HashMap<String, String>| {
+                move |maybe_path_param: Option<String>,
+                      query: HashMap<String, String>,
+                      maybe_last_event_id: Option<String>| {
                     let new_subscriber_info_sender_clone = new_subscriber_info_sender.clone();
                     serve_sse_response_handler(
                         maybe_path_param,
                         query,
-                        cloned_broadcaster.clone(),
+                        maybe_last_event_id,
+                        &cloned_state,
                         max_concurrent_subscribers,
                         new_subscriber_info_sender_clone,
                         #[cfg(feature = "additional-metrics")]
@@ -473,7 +592,7 @@ impl ChannelsAndFilter {
             .boxed();
 
         ChannelsAndFilter {
-            event_broadcaster,
+            state,
             new_subscriber_info_receiver,
             sse_filter,
         }
@@ -943,6 +1062,52 @@ mod tests {
         should_filter_duplicate_events(SSE_API_SIGNATURES_PATH).await
     }
 
+    /// This test checks that a `Last-Event-ID` is only served if the events following it are still
+    /// buffered, including when the buffered IDs wrap around.
+    #[test]
+    fn should_detect_clients_too_far_behind() {
+        assert!(!is_too_far_behind(5, None));
+
+        let range = Some((10, 20));
+        assert!(is_too_far_behind(9, range));
+        assert!(!is_too_far_behind(10, range));
+        assert!(!is_too_far_behind(21, range));
+        assert!(is_too_far_behind(22, range));
+
+        let wrapping_range = Some((Id::MAX - 5, 4));
+        assert!(is_too_far_behind(Id::MAX - 6, wrapping_range));
+        assert!(!is_too_far_behind(Id::MAX, wrapping_range));
+        assert!(!is_too_far_behind(5, wrapping_range));
+        assert!(is_too_far_behind(6, wrapping_range));
+    }
+
+    #[test]
+    fn should_parse_last_event_id() {
+        let range = BufferedIdRange::new(RwLock::new(Some((10, 20))));
+
+        assert_eq!(parse_last_event_id(None, &range).unwrap(), None);
+        assert_eq!(
+            parse_last_event_id(Some(String::new()), &range).unwrap(),
+            None
+        );
+        assert_eq!(
+            parse_last_event_id(Some("15".to_string()), &range).unwrap(),
+            Some(16)
+        );
+        assert_eq!(
+            parse_last_event_id(Some("x".to_string()), &range)
+                .unwrap_err()
+                .status(),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            parse_last_event_id(Some("5".to_string()), &range)
+                .unwrap_err()
+                .status(),
+            StatusCode::GONE
+        );
+    }
+
     // Returns `count` random SSE events, all of a single variant defined by `path_filter`.  The
     // events will have sequential IDs starting from `start_id`, and if the path filter
     // indicates the events should be deploy-accepted ones, the corresponding random deploys
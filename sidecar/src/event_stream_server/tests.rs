@@ -3,10 +3,11 @@ use casper_types::{testing::TestRng, ProtocolVersion};
 use futures::{join, StreamExt};
 use http::StatusCode;
 use pretty_assertions::assert_eq;
+use prometheus::{IntGaugeVec, Opts};
 use reqwest::Response;
 use serde_json::Value;
 use sse_server::{
-    DeployAccepted, Id, QUERY_FIELD, SSE_API_DEPLOYS_PATH as DEPLOYS_PATH,
+    DeployAccepted, Id, LAST_EVENT_ID_HEADER, QUERY_FIELD, SSE_API_DEPLOYS_PATH as DEPLOYS_PATH,
     SSE_API_MAIN_PATH as MAIN_PATH, SSE_API_ROOT_PATH as ROOT_PATH,
     SSE_API_SIGNATURES_PATH as SIGS_PATH,
 };
@@ -196,6 +197,9 @@ struct TestFixture {
     first_event_id: Id,
     server_join_handle: Option<JoinHandle<()>>,
     server_stopper: ServerStopper,
+    /// The server's `sse_subscribers` gauge, kept apart from the registered one so that servers
+    /// run by concurrent tests don't interfere with it.
+    subscribers_gauge: IntGaugeVec,
 }
 
 impl TestFixture {
@@ -232,6 +236,11 @@ impl TestFixture {
             first_event_id: 0,
             server_join_handle: None,
             server_stopper: ServerStopper::new(),
+            subscribers_gauge: IntGaugeVec::new(
+                Opts::new("sse_subscribers", "test subscribers gauge"),
+                &["type"],
+            )
+            .unwrap(),
         }
     }
 
@@ -266,8 +275,12 @@ impl TestFixture {
                 .unwrap_or(Config::default().max_concurrent_subscribers),
             ..Default::default()
         };
-        let mut server =
-            EventStreamServer::new(config, self.storage_dir.path().to_path_buf()).unwrap();
+        let mut server = EventStreamServer::start(
+            config,
+            self.storage_dir.path().to_path_buf(),
+            self.subscribers_gauge.clone(),
+        )
+        .unwrap();
 
         self.first_event_id = server.event_indexer.current_index();
 
@@ -336,6 +349,31 @@ impl TestFixture {
             .expect("server task should not error");
     }
 
+    /// Returns the value of the server's `sse_subscribers` gauge for the given "type" label.
+    fn subscribers_gauge_value(&self, label: &str) -> i64 {
+        self.subscribers_gauge.with_label_values(&[label]).get()
+    }
+
+    /// Waits until the server's `sse_subscribers` gauge for the given "type" label has the
+    /// `expected` value. The server only notices that a client went away some time after it
+    /// disconnected.
+    async fn wait_for_subscribers_gauge_value(&self, label: &str, expected: i64) {
+        time::timeout(MAX_TEST_TIME, async {
+            while self.subscribers_gauge_value(label) != expected {
+                time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap_or_else(|_| {
+            panic!(
+                "sse_subscribers {{type=\"{}\"}} is {} rather than {} (test hung)",
+                label,
+                self.subscribers_gauge_value(label),
+                expected
+            )
+        });
+    }
+
     /// Returns all the events which would have been received by a client via
     /// `/events/<final_path_element>`, where the client connected just before `from` was emitted
     /// from the server.  This includes the initial `ApiVersion` event.
@@ -445,11 +483,46 @@ async fn subscribe(
     final_event_id: Id,
     client_id: &str,
 ) -> Result<Vec<ReceivedEvent>, reqwest::Error> {
-    debug!("{} waiting before connecting via {}", client_id, url);
+    subscribe_with_request(
+        reqwest::Client::new().get(url),
+        barrier,
+        final_event_id,
+        client_id,
+    )
+    .await
+}
+
+// Similar to the `subscribe()` function, except the client sends a `Last-Event-ID` header as if
+// resuming a dropped connection.
+async fn subscribe_with_last_event_id(
+    url: &str,
+    last_event_id: Id,
+    barrier: Arc<Barrier>,
+    final_event_id: Id,
+    client_id: &str,
+) -> Result<Vec<ReceivedEvent>, reqwest::Error> {
+    subscribe_with_request(
+        reqwest::Client::new()
+            .get(url)
+            .header(LAST_EVENT_ID_HEADER, last_event_id.to_string()),
+        barrier,
+        final_event_id,
+        client_id,
+    )
+    .await
+}
+
+async fn subscribe_with_request(
+    request: reqwest::RequestBuilder,
+    barrier: Arc<Barrier>,
+    final_event_id: Id,
+    client_id: &str,
+) -> Result<Vec<ReceivedEvent>, reqwest::Error> {
+    debug!("{} waiting before connecting", client_id);
     timeout(Duration::from_secs(60), barrier.wait())
         .await
         .unwrap();
-    let response = reqwest::get(url).await?;
+    let response = request.send().await?;
     debug!("{} waiting after connecting", client_id);
     timeout(Duration::from_secs(60), barrier.wait())
         .await
@@ -521,6 +594,7 @@ async fn handle_response(
 ) -> Result<Vec<ReceivedEvent>, reqwest::Error> {
     if response.status() == StatusCode::SERVICE_UNAVAILABLE {
         debug!("{} rejected by server: too many clients", client_id);
+        assert!(response.headers().contains_key(http::header::RETRY_AFTER));
         assert_eq!(
             response.text().await.unwrap(),
             "server has reached limit of subscribers"
@@ -694,6 +768,35 @@ async fn should_serve_signature_events_with_query() {
     should_serve_events_with_query(SIGS_PATH).await;
 }
 
+/// Client setup:
+///   * `<IP:port>/events/main` with `Last-Event-ID: 24`
+///   * connected just before event ID 50
+///
+/// Expected to receive main events from ID 25 onwards, as if the client resumed a dropped
+/// connection.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn should_serve_events_after_last_event_id() {
+    let mut rng = TestRng::new();
+    let mut fixture = TestFixture::new(&mut rng);
+
+    let connect_at_event_id = BUFFER_LENGTH;
+    let start_from_event_id = BUFFER_LENGTH / 2;
+
+    let mut server_behavior = ServerBehavior::new();
+    let barrier = server_behavior.add_client_sync_before_event(connect_at_event_id);
+    let server_address = fixture.run_server(server_behavior).await;
+
+    let url = url(server_address, MAIN_PATH, None);
+    let (expected_events, final_id) = fixture.filtered_events(MAIN_PATH, start_from_event_id);
+    let received_events =
+        subscribe_with_last_event_id(&url, start_from_event_id - 1, barrier, final_id, "client")
+            .await
+            .unwrap();
+    fixture.stop_server().await;
+
+    assert_eq!(received_events, expected_events);
+}
+
 /// Client setup:
 ///   * `<IP:port>/events/<path>?start_from=0`
 ///   * connected just before event ID 75
@@ -1114,6 +1217,7 @@ async fn should_limit_concurrent_subscribers() {
     let barrier5 = server_behavior.add_client_sync_before_event(1);
     let barrier6 = server_behavior.add_client_sync_before_event(1);
     let server_address = fixture.run_server(server_behavior).await;
+    assert_eq!(fixture.subscribers_gauge_value("max"), 3);
 
     let url_main = url(server_address, MAIN_PATH, None);
     let url_deploys = url(server_address, DEPLOYS_PATH, None);
@@ -1150,6 +1254,10 @@ async fn should_limit_concurrent_subscribers() {
     assert!(empty_events_deploys.unwrap().is_empty());
     assert!(empty_events_sigs.unwrap().is_empty());
 
+    // The rejected clients were never counted, and the first three stop being counted once their
+    // streams are dropped.
+    fixture.wait_for_subscribers_gauge_value("current", 0).await;
+
     // Check that now the first clients have all disconnected, three new clients can connect.  Have
     // them start from event 80 to allow them to actually pull some events off the stream (as the
     // server has by now stopped creating any new events).
@@ -1174,6 +1282,7 @@ async fn should_limit_concurrent_subscribers() {
     assert_eq!(received_events_main.unwrap(), expected_main_events);
     assert_eq!(received_events_deploys.unwrap(), expected_deploys_events);
     assert_eq!(received_events_sigs.unwrap(), expected_sigs_events);
+    fixture.wait_for_subscribers_gauge_value("current", 0).await;
 
     fixture.stop_server().await;
 }
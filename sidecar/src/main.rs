@@ -134,6 +134,8 @@ fn start_event_broadcasting(
     let event_stream_server_port = config.event_stream_server.port;
     let buffer_length = config.event_stream_server.event_stream_buffer_length;
     let max_concurrent_subscribers = config.event_stream_server.max_concurrent_subscribers;
+    let cors_allowed_origins = config.event_stream_server.cors_allowed_origins.clone();
+    let max_connections_per_second = config.event_stream_server.max_connections_per_second;
     tokio::spawn(async move {
         // Create new instance for the Sidecar's Event Stream Server
         let mut event_stream_server = EventStreamServer::new(
@@ -141,6 +143,8 @@ fn start_event_broadcasting(
                 event_stream_server_port,
                 Some(buffer_length),
                 Some(max_concurrent_subscribers),
+                cors_allowed_origins,
+                max_connections_per_second,
             ),
             PathBuf::from(storage_path),
         )
@@ -311,6 +315,14 @@ fn builder(
         delay_between_attempts: Duration::from_secs(
             connection.delay_between_retries_in_seconds as u64,
         ),
+        max_delay_between_attempts: Duration::from_secs(
+            connection
+                .max_delay_between_retries_in_seconds
+                .unwrap_or(connection.delay_between_retries_in_seconds) as u64,
+        ),
+        delay_jitter: Duration::from_millis(
+            connection.delay_jitter_in_milliseconds.unwrap_or(0) as u64
+        ),
         allow_partial_connection: connection.allow_partial_connection,
         sse_event_sender: inbound_sse_data_sender,
         connection_timeout: Duration::from_secs(
@@ -1,47 +1,350 @@
+mod compression;
 mod errors;
+mod etag;
 pub mod filters;
 mod handlers;
 mod openapi;
 #[cfg(test)]
 mod tests;
 
-use std::net::TcpListener;
-use std::time::Duration;
+use std::convert::Infallible;
+use std::net::{SocketAddr, TcpListener};
+use std::time::{Duration, Instant};
 
-use anyhow::Error;
-use hyper::Server;
-use tower::{buffer::Buffer, make::Shared, ServiceBuilder};
-use warp::Filter;
+use anyhow::{Context, Error};
+use http::{
+    header::{HeaderName, VARY},
+    HeaderMap, HeaderValue, Method, Request, StatusCode,
+};
+use hyper::{
+    server::conn::AddrStream,
+    service::{make_service_fn, Service},
+    Body, Server,
+};
+use serde_json::{Map, Value};
+use tower::{buffer::Buffer, ServiceBuilder};
+use tracing::info;
+use warp::{cors::CorsForbidden, path::FullPath, reply::Response, Filter, Rejection, Reply};
 
+use self::{compression::with_compression, etag::with_etag};
 use crate::{
     types::{config::RestServerConfig, database::DatabaseReader},
-    utils::resolve_address,
+    utils::{
+        cors_with_allowed_origins, count_in_flight, hide_cors_headers_from_disallowed_origins,
+        resolve_address, InvalidCorsOrigin, RateLimitedIncoming,
+    },
 };
 
 const BIND_ALL_INTERFACES: &str = "0.0.0.0";
+/// Address of the client a request came from. The server adds it to the extensions of every
+/// request, since warp only learns it when it runs the server itself.
+#[derive(Clone, Copy, Debug)]
+struct RemoteAddr(SocketAddr);
+
+/// Request header with which a client asks for JSON responses with sorted object keys.
+const CANONICAL_JSON_HEADER: &str = "x-canonical-json";
+const REQUEST_ID_HEADER: &str = "x-request-id";
+const DEFAULT_CORS_MAX_AGE_IN_SECONDS: u64 = 600;
+const DEFAULT_COMPRESSION_MIN_SIZE_IN_BYTES: usize = 1024;
+/// Headers which can't be echoed as they would corrupt the framing or the representation of the
+/// response, or are only meaningful for a single connection.
+const UNECHOABLE_HEADERS: [&str; 11] = [
+    "connection",
+    "content-encoding",
+    "content-length",
+    "content-type",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
 
 pub async fn run_server<Db: DatabaseReader + Clone + Send + Sync + 'static>(
     config: RestServerConfig,
     database: Db,
 ) -> Result<(), Error> {
-    let api = filters::combined_filters(database);
+    let echoed_headers = parse_echoed_headers(config.echoed_headers.unwrap_or_default())?;
+    let cors = cors(
+        config.cors_allowed_origins.as_deref(),
+        config
+            .cors_max_age_in_seconds
+            .unwrap_or(DEFAULT_CORS_MAX_AGE_IN_SECONDS),
+        echoed_headers.clone(),
+    )?;
+    let api = with_compression(
+        with_etag(with_canonical_json(with_echoed_headers(
+            filters::combined_filters(database, config.error_verbosity.unwrap_or_default()),
+            echoed_headers,
+        ))),
+        config
+            .compression_min_size_in_bytes
+            .unwrap_or(DEFAULT_COMPRESSION_MIN_SIZE_IN_BYTES),
+    );
     let address = format!("{}:{}", BIND_ALL_INTERFACES, config.port);
     let socket_address = resolve_address(&address)?;
 
     let listener = TcpListener::bind(socket_address)?;
 
-    let warp_service = warp::service(api.with(warp::cors().allow_any_origin()));
+    let warp_service = warp::service(with_access_log(
+        hide_cors_headers_from_disallowed_origins(api.with(cors), config.cors_allowed_origins)
+            .recover(forbid_rejected_cors_request),
+        config.enable_access_logging.unwrap_or(false),
+    ));
     let tower_service = ServiceBuilder::new()
         .concurrency_limit(config.max_concurrent_requests as usize)
         .rate_limit(
             config.max_requests_per_second as u64,
             Duration::from_secs(1),
         )
+        .map_future(|request| count_in_flight("rest", request))
         .service(warp_service);
 
-    Server::from_tcp(listener)?
-        .serve(Shared::new(Buffer::new(tower_service, 50)))
-        .await?;
+    let service = Buffer::new(tower_service, 50);
+    Server::builder(RateLimitedIncoming::new(
+        listener,
+        config.max_connections_per_second,
+        "rest",
+    )?)
+    .serve(make_service_fn(move |connection: &AddrStream| {
+        let remote_addr = RemoteAddr(connection.remote_addr());
+        let service = service.clone();
+        async move { Ok::<_, Infallible>(with_remote_addr(service, remote_addr)) }
+    }))
+    .await?;
 
     Err(Error::msg("REST server shutting down"))
 }
+
+/// Allows GET requests from `allowed_origins` (or any origin if not configured), once the filter
+/// using it is wrapped with `hide_cors_headers_from_disallowed_origins`. Preflight
+/// responses carry `Access-Control-Max-Age` so browsers can cache them instead of re-issuing a
+/// preflight before every request. The `echoed_headers` may be sent by browsers and are exposed to
+/// them on the response.
+fn cors(
+    allowed_origins: Option<&[String]>,
+    max_age_in_seconds: u64,
+    echoed_headers: Vec<HeaderName>,
+) -> Result<warp::cors::Builder, InvalidCorsOrigin> {
+    Ok(cors_with_allowed_origins(allowed_origins)?
+        .allow_method("GET")
+        .allow_header(CANONICAL_JSON_HEADER)
+        .allow_headers(echoed_headers.clone())
+        .expose_headers(echoed_headers)
+        .max_age(max_age_in_seconds))
+}
+
+fn with_remote_addr<S>(
+    service: S,
+    remote_addr: RemoteAddr,
+) -> impl Service<Request<Body>, Response = S::Response, Error = S::Error, Future = S::Future>
+where
+    S: Service<Request<Body>>,
+{
+    ServiceBuilder::new()
+        .map_request(move |mut request: Request<Body>| {
+            request.extensions_mut().insert(remote_addr);
+            request
+        })
+        .service(service)
+}
+
+/// Answers preflight requests the CORS policy refuses the way warp does by default, so that they
+/// reach the access log like every other request.
+async fn forbid_rejected_cors_request(rejection: Rejection) -> Result<Response, Rejection> {
+    match rejection.find::<CorsForbidden>() {
+        Some(forbidden) => Ok(warp::reply::with_status(
+            forbidden.to_string(),
+            StatusCode::FORBIDDEN,
+        )
+        .into_response()),
+        None => Err(rejection),
+    }
+}
+
+/// Logs every request `filter` replies to, if `enabled`.
+fn with_access_log<F, R>(
+    filter: F,
+    enabled: bool,
+) -> impl Filter<Extract = (Response,), Error = Rejection> + Clone
+where
+    F: Filter<Extract = (R,), Error = Rejection> + Clone + Send + Sync + 'static,
+    R: Reply,
+{
+    warp::any()
+        .map(Instant::now)
+        .and(warp::method())
+        .and(warp::path::full())
+        .and(warp::header::headers_cloned())
+        .and(warp::ext::optional::<RemoteAddr>())
+        .and(filter)
+        .map(
+            move |start: Instant,
+                  method: Method,
+                  path: FullPath,
+                  request_headers: HeaderMap,
+                  remote_addr: Option<RemoteAddr>,
+                  reply: R| {
+                let response = reply.into_response();
+                if enabled {
+                    log_access(
+                        &method,
+                        path.as_str(),
+                        response.status(),
+                        start.elapsed(),
+                        &request_headers,
+                        remote_addr,
+                    );
+                }
+                response
+            },
+        )
+}
+
+/// Emits a single structured log line per request. The `X-Request-Id` request header, if
+/// present, is included so the entry can be correlated with the client's own logs.
+fn log_access(
+    method: &Method,
+    path: &str,
+    status: StatusCode,
+    elapsed: Duration,
+    request_headers: &HeaderMap,
+    remote_addr: Option<RemoteAddr>,
+) {
+    let request_id = request_headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("-");
+    let remote_addr =
+        remote_addr.map_or_else(|| "-".to_string(), |RemoteAddr(addr)| addr.to_string());
+    info!(
+        method = %method,
+        path,
+        status = status.as_u16(),
+        elapsed_ms = elapsed.as_millis() as u64,
+        remote_addr = remote_addr.as_str(),
+        request_id,
+        "REST request"
+    );
+}
+
+fn parse_echoed_headers(header_names: Vec<String>) -> Result<Vec<HeaderName>, Error> {
+    header_names
+        .iter()
+        .map(|name| {
+            let header_name = HeaderName::from_bytes(name.as_bytes())
+                .with_context(|| format!("Invalid header name in echoed_headers: {}", name))?;
+            if UNECHOABLE_HEADERS.contains(&header_name.as_str()) {
+                return Err(Error::msg(format!(
+                    "Header {} in echoed_headers can't be echoed",
+                    name
+                )));
+            }
+            Ok(header_name)
+        })
+        .collect()
+}
+
+/// Copies the headers named in `echoed_headers` from the request to the response, if the request
+/// carries them, replacing any value the response already has for them. Other request headers
+/// are never echoed.
+fn with_echoed_headers<F, R>(
+    api: F,
+    echoed_headers: Vec<HeaderName>,
+) -> impl Filter<Extract = (Response,), Error = Infallible> + Clone
+where
+    F: Filter<Extract = (R,), Error = Infallible> + Clone + Send + Sync + 'static,
+    R: Reply,
+{
+    warp::header::headers_cloned()
+        .and(api)
+        .map(move |request_headers: HeaderMap, reply: R| {
+            let mut response = reply.into_response();
+            for name in &echoed_headers {
+                let mut values = request_headers.get_all(name).iter();
+                if let Some(first_value) = values.next() {
+                    response
+                        .headers_mut()
+                        .insert(name.clone(), first_value.clone());
+                }
+                for value in values {
+                    response.headers_mut().append(name.clone(), value.clone());
+                }
+            }
+            response
+        })
+}
+
+/// If the request carries `X-Canonical-Json: true`, re-serializes the JSON response body with the
+/// keys of every object sorted, so that equal content always produces the same bytes.
+/// Responses to requests without the header are passed through untouched. All responses carry
+/// `Vary: x-canonical-json` so that caches don't mix up the two representations.
+fn with_canonical_json<F, R>(
+    api: F,
+) -> impl Filter<Extract = (Response,), Error = Infallible> + Clone
+where
+    F: Filter<Extract = (R,), Error = Infallible> + Clone + Send + Sync + 'static,
+    R: Reply,
+{
+    warp::header::headers_cloned()
+        .and(api)
+        .and_then(|request_headers: HeaderMap, reply: R| {
+            let response = reply.into_response();
+            async move {
+                let mut response = if is_canonical_json_requested(&request_headers) {
+                    canonicalize_response(response).await
+                } else {
+                    response
+                };
+                response
+                    .headers_mut()
+                    .append(VARY, HeaderValue::from_static(CANONICAL_JSON_HEADER));
+                Ok::<_, Infallible>(response)
+            }
+        })
+}
+
+fn is_canonical_json_requested(request_headers: &HeaderMap) -> bool {
+    request_headers
+        .get(CANONICAL_JSON_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+async fn canonicalize_response(response: Response) -> Response {
+    let (mut parts, body) = response.into_parts();
+    let bytes = match hyper::body::to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(_) => return Response::from_parts(parts, Body::empty()),
+    };
+    let canonical_bytes = serde_json::from_slice::<Value>(&bytes)
+        .ok()
+        .and_then(|value| serde_json::to_vec(&sort_keys(value)).ok());
+    match canonical_bytes {
+        Some(canonical_bytes) => {
+            parts.headers.remove(http::header::CONTENT_LENGTH);
+            Response::from_parts(parts, Body::from(canonical_bytes))
+        }
+        None => Response::from_parts(parts, Body::from(bytes)),
+    }
+}
+
+fn sort_keys(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = map.into_iter().collect();
+            entries.sort_by(|(left, _), (right, _)| left.cmp(right));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, sort_keys(value)))
+                    .collect::<Map<String, Value>>(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(sort_keys).collect()),
+        other => other,
+    }
+}
@@ -0,0 +1,179 @@
+use std::{convert::Infallible, io::Write};
+
+use flate2::{
+    write::{GzEncoder, ZlibEncoder},
+    Compression,
+};
+use http::{
+    header::{ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_LENGTH, VARY},
+    HeaderMap, HeaderValue,
+};
+use hyper::Body;
+use warp::{reply::Response, Filter, Reply};
+
+/// Content codings the REST server can apply, in order of preference.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ContentEncoding {
+    Gzip,
+    Deflate,
+}
+
+impl ContentEncoding {
+    const PREFERENCE_ORDER: [ContentEncoding; 2] =
+        [ContentEncoding::Gzip, ContentEncoding::Deflate];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            ContentEncoding::Gzip => "gzip",
+            ContentEncoding::Deflate => "deflate",
+        }
+    }
+
+    fn encode(&self, bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            ContentEncoding::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(bytes)?;
+                encoder.finish()
+            }
+            // "deflate" in HTTP means the zlib format (RFC 9110, section 8.4.1.2).
+            ContentEncoding::Deflate => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(bytes)?;
+                encoder.finish()
+            }
+        }
+    }
+}
+
+/// Compresses response bodies of at least `min_size_in_bytes` with gzip or deflate, if the
+/// request's `Accept-Encoding` allows one of them. Smaller responses are sent as they are, since
+/// compressing them costs more CPU time than it saves bandwidth. Unsuccessful responses, including
+/// `304 Not Modified`, and empty bodies are never compressed.
+pub(super) fn with_compression<F, R>(
+    api: F,
+    min_size_in_bytes: usize,
+) -> impl Filter<Extract = (Response,), Error = Infallible> + Clone
+where
+    F: Filter<Extract = (R,), Error = Infallible> + Clone + Send + Sync + 'static,
+    R: Reply,
+{
+    warp::header::headers_cloned()
+        .and(api)
+        .and_then(move |request_headers: HeaderMap, reply: R| {
+            let response = reply.into_response();
+            async move {
+                let encoding = match negotiate_encoding(&request_headers) {
+                    Some(encoding)
+                        if response.status().is_success()
+                            && !response.headers().contains_key(CONTENT_ENCODING) =>
+                    {
+                        encoding
+                    }
+                    _ => return Ok::<_, Infallible>(response),
+                };
+                Ok(compress_response(response, encoding, min_size_in_bytes).await)
+            }
+        })
+}
+
+/// Picks the preferred encoding the client accepts. Codings listed with `q=0` are treated as
+/// refused, also when the client accepts `*`.
+fn negotiate_encoding(request_headers: &HeaderMap) -> Option<ContentEncoding> {
+    let (accepted, refused): (Vec<_>, Vec<_>) = request_headers
+        .get_all(ACCEPT_ENCODING)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|coding| {
+            let mut parts = coding.split(';');
+            let name = parts.next()?.trim();
+            let refused = parts.any(|parameter| is_zero_quality(parameter.trim()));
+            Some((name, refused))
+        })
+        .partition(|(_, refused)| !refused);
+    let is_listed = |codings: &[(&str, bool)], encoding: ContentEncoding| {
+        codings
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case(encoding.as_str()))
+    };
+    ContentEncoding::PREFERENCE_ORDER
+        .iter()
+        .copied()
+        .filter(|encoding| !is_listed(&refused, *encoding))
+        .find(|encoding| {
+            is_listed(&accepted, *encoding) || accepted.iter().any(|(name, _)| *name == "*")
+        })
+}
+
+fn is_zero_quality(parameter: &str) -> bool {
+    parameter
+        .strip_prefix("q=")
+        .and_then(|quality| quality.parse::<f32>().ok())
+        .map(|quality| quality == 0.0)
+        .unwrap_or(false)
+}
+
+async fn compress_response(
+    response: Response,
+    encoding: ContentEncoding,
+    min_size_in_bytes: usize,
+) -> Response {
+    let (mut parts, body) = response.into_parts();
+    let bytes = match hyper::body::to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(_) => return Response::from_parts(parts, Body::empty()),
+    };
+    if bytes.is_empty() || bytes.len() < min_size_in_bytes {
+        return Response::from_parts(parts, Body::from(bytes));
+    }
+    match encoding.encode(&bytes) {
+        Ok(compressed) => {
+            parts.headers.remove(CONTENT_LENGTH);
+            parts.headers.insert(
+                CONTENT_ENCODING,
+                HeaderValue::from_static(encoding.as_str()),
+            );
+            parts
+                .headers
+                .append(VARY, HeaderValue::from_static("accept-encoding"));
+            Response::from_parts(parts, Body::from(compressed))
+        }
+        Err(_) => Response::from_parts(parts, Body::from(bytes)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers_with_accept_encoding(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT_ENCODING, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn should_negotiate_preferred_encoding() {
+        let cases = [
+            ("gzip", Some(ContentEncoding::Gzip)),
+            ("deflate, gzip;q=0.5", Some(ContentEncoding::Gzip)),
+            ("deflate", Some(ContentEncoding::Deflate)),
+            ("gzip;q=0, deflate", Some(ContentEncoding::Deflate)),
+            ("*", Some(ContentEncoding::Gzip)),
+            ("gzip;q=0, *", Some(ContentEncoding::Deflate)),
+            ("gzip;q=0, deflate;q=0, *", None),
+            ("br", None),
+            ("identity", None),
+        ];
+        for (accept_encoding, expected) in cases {
+            assert_eq!(
+                negotiate_encoding(&headers_with_accept_encoding(accept_encoding)),
+                expected,
+                "Accept-Encoding: {}",
+                accept_encoding
+            );
+        }
+        assert_eq!(negotiate_encoding(&HeaderMap::new()), None);
+    }
+}
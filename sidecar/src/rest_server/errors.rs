@@ -1,16 +1,19 @@
 use std::convert::Infallible;
 
 use crate::{
-    types::database::DatabaseReadError,
+    types::{config::ErrorVerbosity, database::DatabaseReadError},
     utils::{InvalidPath, Unexpected},
 };
 use http::StatusCode;
 #[cfg(test)]
 use hyper::body::HttpBody;
 use serde::{Deserialize, Serialize};
-use tracing::error;
+use tracing::{error, warn};
 use warp::{reject, Rejection, Reply};
 
+/// Message returned instead of the details of internal errors when `ErrorVerbosity::Terse` is set.
+const TERSE_INTERNAL_ERROR_MESSAGE: &str = "Internal server error";
+
 #[derive(Deserialize, Serialize)]
 struct ApiError {
     code: u16,
@@ -32,9 +35,15 @@ impl reject::Reject for StorageError {}
 /// - Database-related errors
 /// - Invalid request path errors
 /// - Invalid parameters in the request query
-pub(super) async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible> {
+///
+/// With `ErrorVerbosity::Terse` the details of internal (5xx) errors are logged but replaced with a
+/// generic message in the response. The status code is the same in both modes.
+pub(super) async fn handle_rejection(
+    err: Rejection,
+    error_verbosity: ErrorVerbosity,
+) -> Result<impl Reply, Infallible> {
     let code;
-    let message;
+    let mut message;
 
     if let Some(Unexpected(err)) = err.find() {
         let err_msg = format!(
@@ -56,6 +65,11 @@ pub(super) async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infal
         (code, message) = fallback_status_code_and_message(err)
     }
 
+    if error_verbosity == ErrorVerbosity::Terse && code.is_server_error() {
+        warn!(%message, "Omitting internal error details from REST response");
+        message = TERSE_INTERNAL_ERROR_MESSAGE.to_string();
+    }
+
     let json = warp::reply::json(&ApiError {
         code: code.as_u16(),
         message,
@@ -92,7 +106,15 @@ fn status_code_and_err_message_for_read_error(err: &DatabaseReadError) -> (Statu
 
 #[cfg(test)]
 async fn get_api_error_from_rejection(rejection: Rejection) -> ApiError {
-    let response = handle_rejection(rejection)
+    get_api_error_from_rejection_with_verbosity(rejection, ErrorVerbosity::Verbose).await
+}
+
+#[cfg(test)]
+async fn get_api_error_from_rejection_with_verbosity(
+    rejection: Rejection,
+    error_verbosity: ErrorVerbosity,
+) -> ApiError {
+    let response = handle_rejection(rejection, error_verbosity)
         .await
         .expect("Rejection handling should not have failed")
         .into_response();
@@ -172,3 +194,27 @@ regex parse error:
 error: unclosed character class"
     );
 }
+
+#[tokio::test]
+async fn should_omit_internal_error_details_when_terse() {
+    let rejection = reject::custom(StorageError(DatabaseReadError::Unhandled(
+        anyhow::Error::msg("connection to /var/lib/db refused"),
+    )));
+
+    let api_error =
+        get_api_error_from_rejection_with_verbosity(rejection, ErrorVerbosity::Terse).await;
+
+    assert_eq!(api_error.code, 500);
+    assert_eq!(api_error.message, TERSE_INTERNAL_ERROR_MESSAGE);
+}
+
+#[tokio::test]
+async fn should_keep_client_error_details_when_terse() {
+    let rejection = reject::custom(InvalidParam(anyhow::Error::msg("Invalid param provided")));
+
+    let api_error =
+        get_api_error_from_rejection_with_verbosity(rejection, ErrorVerbosity::Terse).await;
+
+    assert_eq!(api_error.code, 400);
+    assert!(api_error.message.contains("Invalid param provided"));
+}
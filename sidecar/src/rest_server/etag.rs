@@ -0,0 +1,103 @@
+use std::convert::Infallible;
+
+use blake2::{
+    digest::{Update, VariableOutput},
+    VarBlake2b,
+};
+use http::{
+    header::{ETAG, IF_NONE_MATCH},
+    HeaderMap, HeaderValue, StatusCode,
+};
+use hyper::Body;
+use warp::{reply::Response, Filter, Reply};
+
+const DIGEST_LENGTH: usize = 16;
+
+/// Tags successful responses with an `ETag` derived from the body and answers requests whose
+/// `If-None-Match` matches it with a bodiless `304 Not Modified`, so clients polling for data
+/// which didn't change don't download it again.
+/// The tag is weak, as the same body may still be sent with different content codings.
+pub(super) fn with_etag<F, R>(
+    api: F,
+) -> impl Filter<Extract = (Response,), Error = Infallible> + Clone
+where
+    F: Filter<Extract = (R,), Error = Infallible> + Clone + Send + Sync + 'static,
+    R: Reply,
+{
+    warp::header::headers_cloned()
+        .and(api)
+        .and_then(|request_headers: HeaderMap, reply: R| {
+            let response = reply.into_response();
+            async move {
+                if response.status() != StatusCode::OK {
+                    return Ok::<_, Infallible>(response);
+                }
+                Ok(tag_response(response, &request_headers).await)
+            }
+        })
+}
+
+async fn tag_response(response: Response, request_headers: &HeaderMap) -> Response {
+    let (mut parts, body) = response.into_parts();
+    let bytes = match hyper::body::to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(_) => return Response::from_parts(parts, Body::empty()),
+    };
+    let etag = etag_for(&bytes);
+    let not_modified = is_matched_by_if_none_match(request_headers, &etag);
+    if let Ok(etag) = HeaderValue::from_str(&etag) {
+        parts.headers.insert(ETAG, etag);
+    }
+    if not_modified {
+        parts.status = StatusCode::NOT_MODIFIED;
+        parts.headers.remove(http::header::CONTENT_LENGTH);
+        parts.headers.remove(http::header::CONTENT_TYPE);
+        return Response::from_parts(parts, Body::empty());
+    }
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+fn etag_for(bytes: &[u8]) -> String {
+    let mut digest = [0u8; DIGEST_LENGTH];
+    let mut hasher = VarBlake2b::new(DIGEST_LENGTH).expect("valid blake2b output length");
+    hasher.update(bytes);
+    hasher.finalize_variable(|hash| digest.clone_from_slice(hash));
+    format!("W/\"{}\"", hex::encode(digest))
+}
+
+/// `If-None-Match` uses weak comparison, so the `W/` prefix is ignored on both sides.
+fn is_matched_by_if_none_match(request_headers: &HeaderMap, etag: &str) -> bool {
+    let etag = etag.trim_start_matches("W/");
+    request_headers
+        .get_all(IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_match_if_none_match_weakly() {
+        let etag = etag_for(b"{\"some\":\"json\"}");
+        let mut request_headers = HeaderMap::new();
+        assert!(!is_matched_by_if_none_match(&request_headers, &etag));
+
+        request_headers.insert(
+            IF_NONE_MATCH,
+            HeaderValue::from_str(&format!("\"other\", {}", etag.trim_start_matches("W/")))
+                .unwrap(),
+        );
+        assert!(is_matched_by_if_none_match(&request_headers, &etag));
+
+        request_headers.insert(IF_NONE_MATCH, HeaderValue::from_static("\"other\""));
+        assert!(!is_matched_by_if_none_match(&request_headers, &etag));
+
+        request_headers.insert(IF_NONE_MATCH, HeaderValue::from_static("*"));
+        assert!(is_matched_by_if_none_match(&request_headers, &etag));
+    }
+}
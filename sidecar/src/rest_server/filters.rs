@@ -1,16 +1,17 @@
 use super::{errors::handle_rejection, handlers, openapi::build_open_api_filters};
 use crate::{
-    types::database::DatabaseReader,
+    types::{config::ErrorVerbosity, database::DatabaseReader},
     utils::{root_filter, InvalidPath},
 };
 use std::convert::Infallible;
 use warp::Filter;
 
 /// Helper function to specify available filters.
-/// Input: the database with data to be filtered.
+/// Input: the database with data to be filtered and the verbosity of returned internal errors.
 /// Return: the filtered data.
 pub(super) fn combined_filters<Db: DatabaseReader + Clone + Send + Sync>(
     db: Db,
+    error_verbosity: ErrorVerbosity,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Infallible> + Clone {
     root_filter()
         .or(root_and_invalid_path())
@@ -21,7 +22,7 @@ pub(super) fn combined_filters<Db: DatabaseReader + Clone + Send + Sync>(
         .or(faults_by_era(db.clone()))
         .or(finality_signatures_by_block(db))
         .or(build_open_api_filters())
-        .recover(handle_rejection)
+        .recover(move |rejection| handle_rejection(rejection, error_verbosity))
 }
 
 /// Handle the case where an invalid path was provided.
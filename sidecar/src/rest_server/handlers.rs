@@ -8,6 +8,10 @@ use anyhow::Error;
 use serde::Serialize;
 use warp::{http::StatusCode, Rejection, Reply};
 
+/// Prefixes clients commonly put in front of hex-encoded hashes, accepted by all hash-based
+/// endpoints.
+const HASH_PREFIXES: [&str; 3] = ["0x", "0X", "hash-"];
+
 pub(super) async fn get_latest_block<Db: DatabaseReader + Clone + Send>(
     db: Db,
 ) -> Result<impl Reply, Rejection> {
@@ -19,7 +23,7 @@ pub(super) async fn get_block_by_hash<Db: DatabaseReader + Clone + Send>(
     hash: String,
     db: Db,
 ) -> Result<impl Reply, Rejection> {
-    check_hash_is_correct_format(&hash)?;
+    let hash = normalize_and_check_hash(&hash)?;
     let db_result = db.get_block_by_hash(&hash).await;
     format_or_reject_storage_result(db_result)
 }
@@ -36,7 +40,7 @@ pub(super) async fn get_deploy_by_hash<Db: DatabaseReader + Clone + Send>(
     hash: String,
     db: Db,
 ) -> Result<impl Reply, Rejection> {
-    check_hash_is_correct_format(&hash)?;
+    let hash = normalize_and_check_hash(&hash)?;
     let db_result = db.get_deploy_aggregate_by_hash(&hash).await;
     format_or_reject_storage_result(db_result)
 }
@@ -45,7 +49,7 @@ pub(super) async fn get_deploy_accepted_by_hash<Db: DatabaseReader + Clone + Sen
     hash: String,
     db: Db,
 ) -> Result<impl Reply, Rejection> {
-    check_hash_is_correct_format(&hash)?;
+    let hash = normalize_and_check_hash(&hash)?;
     let db_result = db.get_deploy_accepted_by_hash(&hash).await;
     format_or_reject_storage_result(db_result)
 }
@@ -54,7 +58,7 @@ pub(super) async fn get_deploy_processed_by_hash<Db: DatabaseReader + Clone + Se
     hash: String,
     db: Db,
 ) -> Result<impl Reply, Rejection> {
-    check_hash_is_correct_format(&hash)?;
+    let hash = normalize_and_check_hash(&hash)?;
     let db_result = db.get_deploy_processed_by_hash(&hash).await;
     format_or_reject_storage_result(db_result)
 }
@@ -63,7 +67,7 @@ pub(super) async fn get_deploy_expired_by_hash<Db: DatabaseReader + Clone + Send
     hash: String,
     db: Db,
 ) -> Result<impl Reply, Rejection> {
-    check_hash_is_correct_format(&hash)?;
+    let hash = normalize_and_check_hash(&hash)?;
     let db_result = db.get_deploy_expired_by_hash(&hash).await;
     format_or_reject_storage_result(db_result)
 }
@@ -97,7 +101,7 @@ pub(super) async fn get_finality_signatures_by_block<Db: DatabaseReader + Clone
     block_hash: String,
     db: Db,
 ) -> Result<impl Reply, Rejection> {
-    check_hash_is_correct_format(&block_hash)?;
+    let block_hash = normalize_and_check_hash(&block_hash)?;
     let db_result = db.get_finality_signatures_by_block(&block_hash).await;
     format_or_reject_storage_result(db_result)
 }
@@ -117,14 +121,27 @@ where
     }
 }
 
-fn check_hash_is_correct_format(hash: &str) -> Result<(), Rejection> {
-    let hash_regex = regex::Regex::new("^([0-9A-Fa-f]){64}$")
+/// Strips a known prefix (`0x`, `0X` or `hash-`) from the provided hash and lower-cases it, then
+/// checks that what remains is a hex-encoded hash.
+/// Returns the normalized hash, which matches the form hashes are stored in.
+fn normalize_and_check_hash(hash: &str) -> Result<String, Rejection> {
+    let normalized_hash = HASH_PREFIXES
+        .iter()
+        .find_map(|prefix| hash.strip_prefix(prefix))
+        .unwrap_or(hash)
+        .to_lowercase();
+    check_hash_is_correct_format(&normalized_hash, hash)?;
+    Ok(normalized_hash)
+}
+
+fn check_hash_is_correct_format(hash: &str, received: &str) -> Result<(), Rejection> {
+    let hash_regex = regex::Regex::new("^([0-9a-f]){64}$")
         .map_err(|err| warp::reject::custom(Unexpected(err.into())))?;
     if !hash_regex.is_match(hash) {
         return Err(warp::reject::custom(InvalidParam(Error::msg(format!(
-            "Expected hex-encoded hash (64 chars), received: {} (length: {})",
-            hash,
-            hash.len()
+            "Expected hex-encoded hash (64 chars, optionally prefixed with '0x' or 'hash-'), received: {} (length: {})",
+            received,
+            received.len()
         )))));
     }
     Ok(())
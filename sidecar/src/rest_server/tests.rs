@@ -1,12 +1,16 @@
 use casper_event_types::FinalitySignature as FinSig;
 use casper_types::AsymmetricType;
 use http::StatusCode;
-use warp::test::request;
+use warp::{test::request, Filter};
 
-use super::filters;
+use super::{
+    compression::with_compression, cors, etag::with_etag, filters, parse_echoed_headers, sort_keys,
+    with_canonical_json, with_echoed_headers,
+};
 use crate::{
     testing::fake_database::FakeDatabase,
-    types::{database::DeployAggregate, sse_events::*},
+    types::{config::ErrorVerbosity, database::DeployAggregate, sse_events::*},
+    utils::hide_cors_headers_from_disallowed_origins,
 };
 
 // Path elements
@@ -29,7 +33,7 @@ const INVALID_PUBLIC_KEY: &str = "not_a_public_key";
 async fn should_respond_to_path_with(request_path: String, expected_status: StatusCode) {
     let database = FakeDatabase::new();
 
-    let api = filters::combined_filters(database);
+    let api = filters::combined_filters(database, ErrorVerbosity::Verbose);
 
     let response = request().path(&request_path).reply(&api).await;
 
@@ -57,7 +61,7 @@ async fn block_root_should_return_valid_data() {
 
     // The database doesn't need to be populated with events for this test as it returns a random BlockAdded for get_latest_block()
 
-    let api = filters::combined_filters(database);
+    let api = filters::combined_filters(database, ErrorVerbosity::Verbose);
 
     let request_path = format!("/{}", BLOCK);
 
@@ -78,7 +82,7 @@ async fn block_by_hash_should_return_valid_data() {
         .await
         .expect("Error populating FakeDatabase");
 
-    let api = filters::combined_filters(database);
+    let api = filters::combined_filters(database, ErrorVerbosity::Verbose);
 
     let request_path = format!("/{}/{}", BLOCK, identifiers.block_added_hash);
 
@@ -93,6 +97,37 @@ async fn block_by_hash_should_return_valid_data() {
     assert_eq!(block_added.hex_encoded_hash(), identifiers.block_added_hash);
 }
 
+#[tokio::test]
+async fn block_by_prefixed_or_uppercase_hash_should_return_valid_data() {
+    let database = FakeDatabase::new();
+
+    let identifiers = database
+        .populate_with_events()
+        .await
+        .expect("Error populating FakeDatabase");
+
+    let api = filters::combined_filters(database, ErrorVerbosity::Verbose);
+
+    let hash = identifiers.block_added_hash;
+    for requested_hash in [
+        format!("0x{}", hash),
+        format!("hash-{}", hash),
+        hash.to_uppercase(),
+    ] {
+        let request_path = format!("/{}/{}", BLOCK, requested_hash);
+
+        let response = request().path(&request_path).reply(&api).await;
+
+        assert!(response.status().is_success(), "hash: {}", requested_hash);
+
+        let body = response.into_body();
+        let block_added = serde_json::from_slice::<BlockAdded>(&body)
+            .expect("Error parsing BlockAdded from response");
+
+        assert_eq!(block_added.hex_encoded_hash(), hash);
+    }
+}
+
 #[tokio::test]
 async fn block_by_height_should_return_valid_data() {
     let database = FakeDatabase::new();
@@ -102,7 +137,7 @@ async fn block_by_height_should_return_valid_data() {
         .await
         .expect("Error populating FakeDatabase");
 
-    let api = filters::combined_filters(database);
+    let api = filters::combined_filters(database, ErrorVerbosity::Verbose);
 
     let request_path = format!("/{}/{}", BLOCK, identifiers.block_added_height);
 
@@ -126,7 +161,7 @@ async fn deploy_by_hash_should_return_valid_data() {
         .await
         .expect("Error populating FakeDatabase");
 
-    let api = filters::combined_filters(database);
+    let api = filters::combined_filters(database, ErrorVerbosity::Verbose);
 
     let request_path = format!("/{}/{}", DEPLOY, identifiers.deploy_accepted_hash);
 
@@ -153,7 +188,7 @@ async fn deploy_accepted_by_hash_should_return_valid_data() {
         .await
         .expect("Error populating FakeDatabase");
 
-    let api = filters::combined_filters(database);
+    let api = filters::combined_filters(database, ErrorVerbosity::Verbose);
 
     let request_path = format!(
         "/{}/{}/{}",
@@ -183,7 +218,7 @@ async fn deploy_processed_by_hash_should_return_valid_data() {
         .await
         .expect("Error populating FakeDatabase");
 
-    let api = filters::combined_filters(database);
+    let api = filters::combined_filters(database, ErrorVerbosity::Verbose);
 
     let request_path = format!(
         "/{}/{}/{}",
@@ -213,7 +248,7 @@ async fn deploy_expired_by_hash_should_return_valid_data() {
         .await
         .expect("Error populating FakeDatabase");
 
-    let api = filters::combined_filters(database);
+    let api = filters::combined_filters(database, ErrorVerbosity::Verbose);
 
     let request_path = format!(
         "/{}/{}/{}",
@@ -243,7 +278,7 @@ async fn step_by_era_should_return_valid_data() {
         .await
         .expect("Error populating FakeDatabase");
 
-    let api = filters::combined_filters(database);
+    let api = filters::combined_filters(database, ErrorVerbosity::Verbose);
 
     let request_path = format!("/{}/{}", STEP, identifiers.step_era_id);
 
@@ -266,7 +301,7 @@ async fn faults_by_public_key_should_return_valid_data() {
         .await
         .expect("Error populating FakeDatabase");
 
-    let api = filters::combined_filters(database);
+    let api = filters::combined_filters(database, ErrorVerbosity::Verbose);
 
     let request_path = format!("/{}/{}", FAULTS, identifiers.fault_public_key);
 
@@ -290,7 +325,7 @@ async fn faults_by_era_should_return_valid_data() {
         .await
         .expect("Error populating FakeDatabase");
 
-    let api = filters::combined_filters(database);
+    let api = filters::combined_filters(database, ErrorVerbosity::Verbose);
 
     let request_path = format!("/{}/{}", FAULTS, identifiers.fault_era_id);
 
@@ -314,7 +349,7 @@ async fn finality_signatures_by_block_should_return_valid_data() {
         .await
         .expect("Error populating FakeDatabase");
 
-    let api = filters::combined_filters(database);
+    let api = filters::combined_filters(database, ErrorVerbosity::Verbose);
 
     let request_path = format!(
         "/{}/{}",
@@ -412,6 +447,13 @@ async fn block_by_invalid_hash_should_return_400() {
     should_respond_to_path_with(request_path, StatusCode::BAD_REQUEST).await
 }
 
+#[tokio::test]
+async fn block_by_hash_with_unknown_prefix_should_return_400() {
+    let request_path = format!("/{}/deploy-{}", BLOCK, VALID_HASH);
+
+    should_respond_to_path_with(request_path, StatusCode::BAD_REQUEST).await
+}
+
 #[tokio::test]
 async fn deploy_by_hash_of_invalid_should_return_400() {
     let request_path = format!("/{}/{}", DEPLOY, INVALID_HASH);
@@ -458,7 +500,7 @@ async fn finality_signature_by_invalid_block_hash_should_return_400() {
 async fn should_have_correct_content_type() {
     let database = FakeDatabase::new();
 
-    let api = filters::combined_filters(database);
+    let api = filters::combined_filters(database, ErrorVerbosity::Verbose);
 
     let request_path = format!("/{}", BLOCK);
 
@@ -472,3 +514,391 @@ async fn should_have_correct_content_type() {
         "application/json"
     );
 }
+
+#[tokio::test]
+async fn should_echo_only_configured_headers() {
+    let database = FakeDatabase::new();
+
+    let echoed_headers = parse_echoed_headers(vec!["X-Request-Id".to_string()]).unwrap();
+    let api = with_echoed_headers(
+        filters::combined_filters(database, ErrorVerbosity::Verbose),
+        echoed_headers,
+    );
+
+    let request_path = format!("/{}", BLOCK);
+
+    let response = request()
+        .path(&request_path)
+        .header("x-request-id", "abc-123")
+        .header("x-tenant-id", "tenant")
+        .reply(&api)
+        .await;
+
+    assert!(response.status().is_success());
+    assert_eq!(response.headers().get("x-request-id").unwrap(), "abc-123");
+    assert!(response.headers().get("x-tenant-id").is_none());
+}
+
+#[tokio::test]
+async fn should_replace_response_headers_with_echoed_ones() {
+    let echoed_headers = parse_echoed_headers(vec!["X-Request-Id".to_string()]).unwrap();
+    let api = with_echoed_headers(
+        warp::any().map(|| warp::reply::with_header("ok", "x-request-id", "server")),
+        echoed_headers,
+    );
+
+    let response = request()
+        .header("x-request-id", "abc-123")
+        .reply(&api)
+        .await;
+
+    let values: Vec<_> = response.headers().get_all("x-request-id").iter().collect();
+    assert_eq!(values, vec!["abc-123"]);
+}
+
+#[test]
+fn should_reject_invalid_echoed_header_name() {
+    assert!(parse_echoed_headers(vec!["not a header".to_string()]).is_err());
+}
+
+#[test]
+fn should_reject_framing_and_hop_by_hop_echoed_headers() {
+    for name in &[
+        "Content-Length",
+        "content-type",
+        "Transfer-Encoding",
+        "Connection",
+        "keep-alive",
+    ] {
+        assert!(parse_echoed_headers(vec![name.to_string()]).is_err());
+    }
+}
+
+#[tokio::test]
+async fn should_expose_echoed_headers_to_browsers() {
+    let database = FakeDatabase::new();
+
+    let echoed_headers = parse_echoed_headers(vec!["X-Request-Id".to_string()]).unwrap();
+    let api = filters::combined_filters(database, ErrorVerbosity::Verbose)
+        .with(cors(None, 600, echoed_headers).unwrap());
+
+    let request_path = format!("/{}", BLOCK);
+
+    let response = request()
+        .path(&request_path)
+        .header("origin", "https://example.com")
+        .reply(&api)
+        .await;
+
+    assert!(response.status().is_success());
+    assert_eq!(
+        response
+            .headers()
+            .get("access-control-expose-headers")
+            .unwrap(),
+        "x-request-id"
+    );
+}
+
+#[tokio::test]
+async fn should_sort_response_keys_when_canonical_json_requested() {
+    let database = FakeDatabase::new();
+
+    let api = with_canonical_json(filters::combined_filters(database, ErrorVerbosity::Verbose));
+
+    let request_path = format!("/{}", BLOCK);
+
+    let response = request()
+        .path(&request_path)
+        .header("x-canonical-json", "true")
+        .reply(&api)
+        .await;
+
+    assert!(response.status().is_success());
+    let body = std::str::from_utf8(response.body()).unwrap();
+    let value: serde_json::Value = serde_json::from_str(body).unwrap();
+    assert_eq!(body, serde_json::to_string(&sort_keys(value)).unwrap());
+}
+
+#[tokio::test]
+async fn should_vary_on_canonical_json_header() {
+    let database = FakeDatabase::new();
+
+    let api = with_canonical_json(filters::combined_filters(database, ErrorVerbosity::Verbose));
+
+    let request_path = format!("/{}", BLOCK);
+
+    for canonical_json in &["true", "false"] {
+        let response = request()
+            .path(&request_path)
+            .header("x-canonical-json", *canonical_json)
+            .reply(&api)
+            .await;
+
+        assert_eq!(response.headers().get("vary").unwrap(), "x-canonical-json");
+    }
+}
+
+#[test]
+fn should_sort_nested_keys() {
+    let value = serde_json::json!({"b": 1, "a": [{"d": 2, "c": 3}]});
+
+    let sorted = serde_json::to_string(&sort_keys(value)).unwrap();
+
+    assert_eq!(sorted, r#"{"a":[{"c":3,"d":2}],"b":1}"#);
+}
+
+#[tokio::test]
+async fn preflight_response_should_carry_max_age() {
+    let database = FakeDatabase::new();
+
+    let api = filters::combined_filters(database, ErrorVerbosity::Verbose)
+        .with(cors(None, 600, vec![]).unwrap());
+
+    let request_path = format!("/{}", BLOCK);
+
+    let response = request()
+        .method("OPTIONS")
+        .path(&request_path)
+        .header("origin", "https://example.com")
+        .header("access-control-request-method", "GET")
+        .reply(&api)
+        .await;
+
+    assert!(response.status().is_success());
+    assert_eq!(
+        response.headers().get("access-control-max-age").unwrap(),
+        "600"
+    );
+}
+
+#[tokio::test]
+async fn should_only_allow_configured_origins() {
+    let database = FakeDatabase::new();
+    let allowed_origins = vec![
+        "https://app.example.com".to_string(),
+        "http://localhost:3000".to_string(),
+    ];
+
+    let api = hide_cors_headers_from_disallowed_origins(
+        filters::combined_filters(database, ErrorVerbosity::Verbose)
+            .with(cors(Some(&allowed_origins), 600, vec![]).unwrap()),
+        Some(allowed_origins.clone()),
+    );
+
+    let request_path = format!("/{}", BLOCK);
+
+    for origin in &allowed_origins {
+        let response = request()
+            .path(&request_path)
+            .header("origin", origin)
+            .reply(&api)
+            .await;
+        assert!(response.status().is_success());
+        assert_eq!(
+            response
+                .headers()
+                .get("access-control-allow-origin")
+                .unwrap(),
+            origin.as_str()
+        );
+    }
+
+    let response = request()
+        .path(&request_path)
+        .header("origin", "https://evil.example.com")
+        .reply(&api)
+        .await;
+    assert!(response.status().is_success());
+    assert!(!response.body().is_empty());
+    assert!(response
+        .headers()
+        .get("access-control-allow-origin")
+        .is_none());
+    assert_eq!(response.headers().get("vary").unwrap(), "origin");
+
+    let response = request()
+        .method("OPTIONS")
+        .path(&request_path)
+        .header("origin", "https://evil.example.com")
+        .header("access-control-request-method", "GET")
+        .reply(&api)
+        .await;
+    assert!(response
+        .headers()
+        .keys()
+        .all(|name| !name.as_str().starts_with("access-control-")));
+}
+
+#[tokio::test]
+async fn should_match_configured_origins_case_insensitively() {
+    let database = FakeDatabase::new();
+    let allowed_origins = vec![
+        "https://App.example.com".to_string(),
+        "HTTP://localhost:3000".to_string(),
+    ];
+
+    let api = hide_cors_headers_from_disallowed_origins(
+        filters::combined_filters(database, ErrorVerbosity::Verbose)
+            .with(cors(Some(&allowed_origins), 600, vec![]).unwrap()),
+        Some(allowed_origins),
+    );
+
+    for origin in ["https://app.example.com", "http://localhost:3000"] {
+        let response = request()
+            .path(&format!("/{}", BLOCK))
+            .header("origin", origin)
+            .reply(&api)
+            .await;
+        assert!(response.status().is_success());
+        assert_eq!(
+            response
+                .headers()
+                .get("access-control-allow-origin")
+                .unwrap(),
+            origin
+        );
+    }
+}
+
+#[test]
+fn should_reject_invalid_cors_origins() {
+    for origin in [
+        "app.example.com",
+        "ftp://example.com",
+        "https://example.com/path",
+    ] {
+        assert!(cors(Some(&[origin.to_string()]), 600, vec![]).is_err());
+    }
+}
+
+#[tokio::test]
+async fn should_compress_responses_above_threshold() {
+    use std::io::Read;
+
+    let database = FakeDatabase::new();
+
+    let identifiers = database
+        .populate_with_events()
+        .await
+        .expect("Error populating FakeDatabase");
+
+    let request_path = format!("/{}/{}", BLOCK, identifiers.block_added_hash);
+
+    let uncompressed = request()
+        .path(&request_path)
+        .reply(&filters::combined_filters(
+            database.clone(),
+            ErrorVerbosity::Verbose,
+        ))
+        .await;
+
+    let api = with_compression(
+        filters::combined_filters(database.clone(), ErrorVerbosity::Verbose),
+        0,
+    );
+    let response = request()
+        .path(&request_path)
+        .header("accept-encoding", "gzip")
+        .reply(&api)
+        .await;
+
+    assert!(response.status().is_success());
+    assert_eq!(response.headers().get("content-encoding").unwrap(), "gzip");
+    let mut decompressed = Vec::new();
+    flate2::read::GzDecoder::new(response.body().as_ref())
+        .read_to_end(&mut decompressed)
+        .unwrap();
+    assert_eq!(decompressed, uncompressed.body().as_ref());
+
+    let api = with_compression(
+        filters::combined_filters(database, ErrorVerbosity::Verbose),
+        usize::MAX,
+    );
+    let response = request()
+        .path(&request_path)
+        .header("accept-encoding", "gzip")
+        .reply(&api)
+        .await;
+
+    assert!(response.status().is_success());
+    assert!(response.headers().get("content-encoding").is_none());
+}
+
+#[tokio::test]
+async fn should_not_compress_unsuccessful_or_empty_responses() {
+    let database = FakeDatabase::new();
+
+    let identifiers = database
+        .populate_with_events()
+        .await
+        .expect("Error populating FakeDatabase");
+
+    let api = with_compression(
+        with_etag(filters::combined_filters(database, ErrorVerbosity::Verbose)),
+        0,
+    );
+
+    let request_path = format!("/{}/{}", BLOCK, identifiers.block_added_hash);
+    let etag = request()
+        .path(&request_path)
+        .reply(&api)
+        .await
+        .headers()
+        .get("etag")
+        .unwrap()
+        .clone();
+
+    let response = request()
+        .path(&request_path)
+        .header("accept-encoding", "gzip")
+        .header("if-none-match", etag)
+        .reply(&api)
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert!(response.headers().get("content-encoding").is_none());
+    assert!(response.body().is_empty());
+
+    let response = request()
+        .path(&format!("/{}/{}", BLOCK, INVALID_HASH))
+        .header("accept-encoding", "gzip")
+        .reply(&api)
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(response.headers().get("content-encoding").is_none());
+}
+
+#[tokio::test]
+async fn should_answer_matching_if_none_match_with_304() {
+    let database = FakeDatabase::new();
+
+    let identifiers = database
+        .populate_with_events()
+        .await
+        .expect("Error populating FakeDatabase");
+
+    let api = with_etag(filters::combined_filters(database, ErrorVerbosity::Verbose));
+
+    let request_path = format!("/{}/{}", BLOCK, identifiers.block_added_hash);
+
+    let response = request().path(&request_path).reply(&api).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let etag = response.headers().get("etag").unwrap().clone();
+
+    let response = request()
+        .path(&request_path)
+        .header("if-none-match", etag.clone())
+        .reply(&api)
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(response.headers().get("etag").unwrap(), etag);
+    assert!(response.body().is_empty());
+
+    let response = request()
+        .path(&request_path)
+        .header("if-none-match", "W/\"stale\"")
+        .reply(&api)
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(!response.body().is_empty());
+}
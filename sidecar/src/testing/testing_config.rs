@@ -79,6 +79,8 @@ impl TestingConfig {
             rest_port: rest_port.unwrap_or(random_port_for_rest),
             max_attempts: 2,
             delay_between_retries_in_seconds: 0,
+            max_delay_between_retries_in_seconds: None,
+            delay_jitter_in_milliseconds: None,
             allow_partial_connection: false,
             enable_logging: false,
             connection_timeout_in_seconds: Some(100),
@@ -268,7 +268,7 @@ async fn performance_check(scenario: Scenario, duration: Duration, acceptable_la
     let (_shutdown_tx, _after_shutdown_rx) =
         setup_mock_build_version_server(node_port_for_rest_connection).await;
 
-    let ess_config = EssConfig::new(node_port_for_sse_connection, None, None);
+    let ess_config = EssConfig::new(node_port_for_sse_connection, None, None, None, None);
 
     tokio::spawn(spin_up_fake_event_stream(test_rng, ess_config, scenario));
 
@@ -286,6 +286,8 @@ async fn performance_check(scenario: Scenario, duration: Duration, acceptable_la
         node: node_interface,
         max_connection_attempts: 5,
         delay_between_attempts: Duration::from_secs(1),
+        max_delay_between_attempts: Duration::from_secs(1),
+        delay_jitter: Duration::ZERO,
         allow_partial_connection: false,
         sse_event_sender: node_event_tx,
         connection_timeout: Duration::from_secs(100),
@@ -313,6 +315,8 @@ async fn performance_check(scenario: Scenario, duration: Duration, acceptable_la
         node: sidecar_node_interface,
         max_connection_attempts: 5,
         delay_between_attempts: Duration::from_secs(1),
+        max_delay_between_attempts: Duration::from_secs(1),
+        delay_jitter: Duration::ZERO,
         allow_partial_connection: false,
         sse_event_sender: sidecar_event_tx,
         connection_timeout: Duration::from_secs(100),
@@ -656,7 +660,7 @@ async fn fake_event_stream(
     let test_rng = TestRng::new();
     let (_shutdown_tx, _after_shutdown_rx) =
         setup_mock_build_version_server(node_port_for_rest_connection).await;
-    let ess_config = EssConfig::new(node_port_for_sse_connection, None, None);
+    let ess_config = EssConfig::new(node_port_for_sse_connection, None, None, None, None);
     spin_up_fake_event_stream(test_rng, ess_config, scenario).await;
 }
 
@@ -75,6 +75,8 @@ pub struct Connection {
     pub rest_port: u16,
     pub max_attempts: usize,
     pub delay_between_retries_in_seconds: usize,
+    pub max_delay_between_retries_in_seconds: Option<usize>,
+    pub delay_jitter_in_milliseconds: Option<usize>,
     pub allow_partial_connection: bool,
     pub enable_logging: bool,
     pub connection_timeout_in_seconds: Option<usize>,
@@ -252,6 +254,24 @@ pub struct RestServerConfig {
     pub port: u16,
     pub max_concurrent_requests: u32,
     pub max_requests_per_second: u32,
+    pub error_verbosity: Option<ErrorVerbosity>,
+    pub echoed_headers: Option<Vec<String>>,
+    pub cors_max_age_in_seconds: Option<u64>,
+    pub enable_access_logging: Option<bool>,
+    pub cors_allowed_origins: Option<Vec<String>>,
+    pub compression_min_size_in_bytes: Option<usize>,
+    pub max_connections_per_second: Option<u32>,
+}
+
+/// How much detail the REST server includes in the message of internal (5xx) errors.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorVerbosity {
+    /// Internal error details are only logged, the response carries a generic message.
+    Terse,
+    /// The response carries the full internal error details.
+    #[default]
+    Verbose,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
@@ -259,6 +279,8 @@ pub struct EventStreamServerConfig {
     pub port: u16,
     pub max_concurrent_subscribers: u32,
     pub event_stream_buffer_length: u32,
+    pub cors_allowed_origins: Option<Vec<String>>,
+    pub max_connections_per_second: Option<u32>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
@@ -266,6 +288,47 @@ pub struct AdminServerConfig {
     pub port: u16,
     pub max_concurrent_requests: u32,
     pub max_requests_per_second: u32,
+    pub max_connections_per_second: Option<u32>,
+    pub metrics_auth: Option<MetricsAuthConfig>,
+}
+
+/// Credentials required to scrape the admin server's `/metrics` endpoint.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(try_from = "MetricsAuthConfigSerdeTarget")]
+pub enum MetricsAuthConfig {
+    BearerToken { bearer_token: String },
+    BasicAuth { username: String, password: String },
+}
+
+#[derive(Deserialize)]
+#[serde(untagged, deny_unknown_fields)]
+pub enum MetricsAuthConfigSerdeTarget {
+    BearerToken { bearer_token: String },
+    BasicAuth { username: String, password: String },
+}
+
+impl TryFrom<MetricsAuthConfigSerdeTarget> for MetricsAuthConfig {
+    type Error = &'static str;
+
+    fn try_from(value: MetricsAuthConfigSerdeTarget) -> Result<Self, Self::Error> {
+        match value {
+            MetricsAuthConfigSerdeTarget::BearerToken { bearer_token } => {
+                if bearer_token.is_empty() {
+                    return Err("metrics_auth.bearer_token must not be empty");
+                }
+                Ok(MetricsAuthConfig::BearerToken { bearer_token })
+            }
+            MetricsAuthConfigSerdeTarget::BasicAuth { username, password } => {
+                if username.is_empty() {
+                    return Err("metrics_auth.username must not be empty");
+                }
+                if password.is_empty() {
+                    return Err("metrics_auth.password must not be empty");
+                }
+                Ok(MetricsAuthConfig::BasicAuth { username, password })
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -336,6 +399,8 @@ mod tests {
                 port: 18887,
                 max_concurrent_requests: 1,
                 max_requests_per_second: 1,
+                max_connections_per_second: None,
+                metrics_auth: None,
             }),
         };
         let parsed_config: Config = read_config("../EXAMPLE_NODE_CONFIG.toml")
@@ -346,11 +411,120 @@ mod tests {
         assert_eq!(parsed_config, expected_config);
     }
 
+    #[test]
+    fn should_parse_admin_server_metrics_auth() {
+        let bearer_config: AdminServerConfig = toml::from_str(
+            r#"
+            port = 18887
+            max_concurrent_requests = 1
+            max_requests_per_second = 1
+            [metrics_auth]
+            bearer_token = "secret"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            bearer_config.metrics_auth,
+            Some(MetricsAuthConfig::BearerToken {
+                bearer_token: "secret".to_string()
+            })
+        );
+
+        let basic_auth_config: AdminServerConfig = toml::from_str(
+            r#"
+            port = 18887
+            max_concurrent_requests = 1
+            max_requests_per_second = 1
+            [metrics_auth]
+            username = "prometheus"
+            password = "p@$$w0rd"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            basic_auth_config.metrics_auth,
+            Some(MetricsAuthConfig::BasicAuth {
+                username: "prometheus".to_string(),
+                password: "p@$$w0rd".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn should_reject_empty_metrics_credentials() {
+        let empty_bearer_token = toml::from_str::<AdminServerConfig>(
+            r#"
+            port = 18887
+            max_concurrent_requests = 1
+            max_requests_per_second = 1
+            [metrics_auth]
+            bearer_token = ""
+            "#,
+        );
+        assert!(empty_bearer_token
+            .unwrap_err()
+            .to_string()
+            .contains("bearer_token must not be empty"));
+
+        let empty_password = toml::from_str::<AdminServerConfig>(
+            r#"
+            port = 18887
+            max_concurrent_requests = 1
+            max_requests_per_second = 1
+            [metrics_auth]
+            username = "prometheus"
+            password = ""
+            "#,
+        );
+        assert!(empty_password
+            .unwrap_err()
+            .to_string()
+            .contains("password must not be empty"));
+
+        let empty_username = toml::from_str::<AdminServerConfig>(
+            r#"
+            port = 18887
+            max_concurrent_requests = 1
+            max_requests_per_second = 1
+            [metrics_auth]
+            username = ""
+            password = "p@$$w0rd"
+            "#,
+        );
+        assert!(empty_username
+            .unwrap_err()
+            .to_string()
+            .contains("username must not be empty"));
+    }
+
+    #[test]
+    fn should_reject_mixed_metrics_credentials() {
+        let mixed_credentials = toml::from_str::<AdminServerConfig>(
+            r#"
+            port = 18887
+            max_concurrent_requests = 1
+            max_requests_per_second = 1
+            [metrics_auth]
+            bearer_token = "secret"
+            username = "prometheus"
+            password = "p@$$w0rd"
+            "#,
+        );
+        assert!(mixed_credentials.is_err());
+    }
+
     fn build_rest_server_config() -> RestServerConfig {
         RestServerConfig {
             port: 18888,
             max_concurrent_requests: 50,
             max_requests_per_second: 50,
+            error_verbosity: None,
+            echoed_headers: None,
+            cors_max_age_in_seconds: None,
+            enable_access_logging: None,
+            cors_allowed_origins: None,
+            compression_min_size_in_bytes: None,
+            max_connections_per_second: None,
         }
     }
 
@@ -362,6 +536,8 @@ mod tests {
                 rest_port: 14101,
                 max_attempts: 10,
                 delay_between_retries_in_seconds: 5,
+                max_delay_between_retries_in_seconds: None,
+                delay_jitter_in_milliseconds: None,
                 allow_partial_connection: false,
                 enable_logging: true,
                 connection_timeout_in_seconds: None,
@@ -377,6 +553,8 @@ mod tests {
                 rest_port: 14102,
                 max_attempts: 10,
                 delay_between_retries_in_seconds: 5,
+                max_delay_between_retries_in_seconds: None,
+                delay_jitter_in_milliseconds: None,
                 allow_partial_connection: false,
                 enable_logging: false,
                 connection_timeout_in_seconds: None,
@@ -392,6 +570,8 @@ mod tests {
                 rest_port: 14103,
                 max_attempts: 10,
                 delay_between_retries_in_seconds: 5,
+                max_delay_between_retries_in_seconds: None,
+                delay_jitter_in_milliseconds: None,
                 allow_partial_connection: false,
                 enable_logging: false,
                 connection_timeout_in_seconds: Some(3),
@@ -410,6 +590,8 @@ mod tests {
                 allow_partial_connection: false,
                 max_attempts: 3,
                 delay_between_retries_in_seconds: 5,
+                max_delay_between_retries_in_seconds: None,
+                delay_jitter_in_milliseconds: None,
                 enable_logging: false,
                 connection_timeout_in_seconds: None,
                 sleep_between_keep_alive_checks_in_seconds: None,
@@ -443,6 +625,13 @@ mod tests {
                 port: 17777,
                 max_concurrent_requests: 50,
                 max_requests_per_second: 50,
+                error_verbosity: None,
+                echoed_headers: None,
+                cors_max_age_in_seconds: None,
+                enable_access_logging: None,
+                cors_allowed_origins: None,
+                compression_min_size_in_bytes: None,
+                max_connections_per_second: None,
             }
         }
     }
@@ -453,6 +642,8 @@ mod tests {
                 port: 19999,
                 max_concurrent_subscribers: 100,
                 event_stream_buffer_length: 5000,
+                cors_allowed_origins: None,
+                max_connections_per_second: None,
             }
         }
     }
@@ -1,15 +1,28 @@
 #[cfg(feature = "additional-metrics")]
 use crate::metrics::EVENTS_PROCESSED_PER_SECOND;
+use casper_event_types::metrics::{INCOMING_CONNECTIONS, IN_FLIGHT_REQUESTS};
+use http::{
+    header::{HeaderValue, ORIGIN, VARY},
+    uri::Authority,
+    HeaderMap,
+};
+use hyper::server::{
+    accept::Accept,
+    conn::{AddrIncoming, AddrStream},
+};
+use prometheus::IntGauge;
 #[cfg(feature = "additional-metrics")]
 use std::sync::Arc;
 #[cfg(feature = "additional-metrics")]
 use std::time::Duration;
-#[cfg(feature = "additional-metrics")]
-use std::time::Instant;
 use std::{
     fmt::{self, Debug, Display, Formatter},
+    future::Future,
     io,
-    net::{SocketAddr, ToSocketAddrs},
+    net::{SocketAddr, TcpListener, ToSocketAddrs},
+    pin::Pin,
+    task::{Context, Poll},
+    time::Instant,
 };
 use thiserror::Error;
 #[cfg(feature = "additional-metrics")]
@@ -17,7 +30,8 @@ use tokio::sync::{
     mpsc::{channel, Sender},
     Mutex,
 };
-use warp::{reject, Filter};
+use tracing::debug;
+use warp::{reject, reply::Response, Filter, Rejection, Reply};
 
 #[derive(Debug)]
 pub struct Unexpected(pub(super) anyhow::Error);
@@ -81,6 +95,10 @@ pub(crate) enum ListeningError {
     #[error("failed to resolve network address: {0}")]
     ResolveAddress(ResolveAddressError),
 
+    /// Invalid CORS configuration.
+    #[error("invalid CORS configuration: {0}")]
+    Cors(InvalidCorsOrigin),
+
     /// Failed to listen.
     #[error("failed to listen on {address}: {error}")]
     Listen {
@@ -91,6 +109,198 @@ pub(crate) enum ListeningError {
     },
 }
 
+/// Origin in the CORS configuration which isn't of the `scheme://host[:port]` form.
+#[derive(Debug, Error)]
+#[error("invalid origin `{0}`, expected `http://host[:port]` or `https://host[:port]`")]
+pub struct InvalidCorsOrigin(String);
+
+/// Builds the CORS policy shared by the REST and SSE servers.
+/// The policy itself allows any origin, since warp rejects requests from origins it doesn't allow
+/// with a `403`. Restricting the origins to `allowed_origins` is left to
+/// [`hide_cors_headers_from_disallowed_origins`], which has to wrap the filter using the policy.
+pub fn cors_with_allowed_origins(
+    allowed_origins: Option<&[String]>,
+) -> Result<warp::cors::Builder, InvalidCorsOrigin> {
+    for origin in allowed_origins.unwrap_or_default() {
+        check_cors_origin(origin)?;
+    }
+    Ok(warp::cors().allow_any_origin())
+}
+
+/// Strips the CORS headers from responses to requests whose `Origin` isn't one of
+/// `allowed_origins` (if configured). Such requests are served as usual, but browsers won't let
+/// the calling page read the response. Origins are compared case-insensitively, as their scheme
+/// and host are.
+pub fn hide_cors_headers_from_disallowed_origins<F, R>(
+    filter_with_cors: F,
+    allowed_origins: Option<Vec<String>>,
+) -> impl Filter<Extract = (Response,), Error = Rejection> + Clone
+where
+    F: Filter<Extract = (R,), Error = Rejection> + Clone + Send + Sync + 'static,
+    R: Reply,
+{
+    warp::header::headers_cloned().and(filter_with_cors).map(
+        move |request_headers: HeaderMap, reply: R| {
+            let mut response = reply.into_response();
+            let origins = match &allowed_origins {
+                Some(origins) => origins,
+                None => return response,
+            };
+            let is_allowed = request_headers.get(ORIGIN).map_or(true, |origin| {
+                origins
+                    .iter()
+                    .any(|allowed| origin.as_bytes().eq_ignore_ascii_case(allowed.as_bytes()))
+            });
+            if !is_allowed {
+                let cors_headers: Vec<_> = response
+                    .headers()
+                    .keys()
+                    .filter(|name| name.as_str().starts_with("access-control-"))
+                    .cloned()
+                    .collect();
+                for name in cors_headers {
+                    response.headers_mut().remove(name);
+                }
+            }
+            // The CORS headers depend on the request's origin, so caches must not share the
+            // response between origins.
+            response
+                .headers_mut()
+                .append(VARY, HeaderValue::from_static("origin"));
+            response
+        },
+    )
+}
+
+fn check_cors_origin(origin: &str) -> Result<(), InvalidCorsOrigin> {
+    let is_valid = match origin.split_once("://") {
+        Some((scheme, authority)) => {
+            matches!(scheme.to_ascii_lowercase().as_str(), "http" | "https")
+                && !authority.contains('/')
+                && authority.parse::<Authority>().is_ok()
+        }
+        None => false,
+    };
+    if is_valid {
+        Ok(())
+    } else {
+        Err(InvalidCorsOrigin(origin.to_string()))
+    }
+}
+
+/// Runs `request` of the server `server_name`, counting it in the `in_flight_requests` metric
+/// until it completes or is dropped, e.g. because the client went away.
+pub(crate) async fn count_in_flight<F: Future>(server_name: &'static str, request: F) -> F::Output {
+    let _in_flight =
+        DecrementOnDrop::increment(IN_FLIGHT_REQUESTS.with_label_values(&[server_name]));
+    request.await
+}
+
+/// Increments a gauge and decrements it again when dropped.
+pub(crate) struct DecrementOnDrop(IntGauge);
+
+impl DecrementOnDrop {
+    pub(crate) fn increment(gauge: IntGauge) -> Self {
+        gauge.inc();
+        DecrementOnDrop(gauge)
+    }
+}
+
+impl Drop for DecrementOnDrop {
+    fn drop(&mut self) {
+        self.0.dec();
+    }
+}
+
+/// Accepts the connections of one of the HTTP servers. Connections beyond
+/// `max_connections_per_second` (if set) are closed right away, before any request is read from
+/// them. Accepted and rejected connections are counted in the `incoming_connections` metric.
+pub(crate) struct RateLimitedIncoming {
+    incoming: AddrIncoming,
+    rate_limiter: Option<ConnectionRateLimiter>,
+    server_name: &'static str,
+}
+
+impl RateLimitedIncoming {
+    pub(crate) fn new(
+        listener: TcpListener,
+        max_connections_per_second: Option<u32>,
+        server_name: &'static str,
+    ) -> Result<Self, anyhow::Error> {
+        listener.set_nonblocking(true)?;
+        let incoming = AddrIncoming::from_listener(tokio::net::TcpListener::from_std(listener)?)?;
+        Ok(RateLimitedIncoming {
+            incoming,
+            rate_limiter: max_connections_per_second.map(ConnectionRateLimiter::new),
+            server_name,
+        })
+    }
+}
+
+impl Accept for RateLimitedIncoming {
+    type Conn = AddrStream;
+    type Error = io::Error;
+
+    fn poll_accept(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
+        loop {
+            let connection = match Pin::new(&mut self.incoming).poll_accept(cx) {
+                Poll::Ready(Some(Ok(connection))) => connection,
+                other => return other,
+            };
+            let is_allowed = match self.rate_limiter.as_mut() {
+                Some(rate_limiter) => rate_limiter.try_acquire(Instant::now()),
+                None => true,
+            };
+            let outcome = if is_allowed { "accepted" } else { "rejected" };
+            INCOMING_CONNECTIONS
+                .with_label_values(&[self.server_name, outcome])
+                .inc();
+            if is_allowed {
+                return Poll::Ready(Some(Ok(connection)));
+            }
+            debug!(
+                server = self.server_name,
+                remote_address = %connection.remote_addr(),
+                "connection rate limit exceeded: closing connection"
+            );
+        }
+    }
+}
+
+/// Token bucket refilled at `max_per_second` tokens per second, allowing bursts of up to one
+/// second's worth of connections.
+struct ConnectionRateLimiter {
+    max_per_second: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl ConnectionRateLimiter {
+    fn new(max_per_second: u32) -> Self {
+        ConnectionRateLimiter {
+            max_per_second: max_per_second as f64,
+            tokens: max_per_second as f64,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn try_acquire(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.tokens =
+            (self.tokens + elapsed.as_secs_f64() * self.max_per_second).min(self.max_per_second);
+        self.last_refill = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
 /// Handle the case where no filter URL was specified after the root address (HOST:PORT).
 /// Return: a message that an invalid path was provided.
 /// Example: curl http://127.0.0.1:18888
@@ -154,8 +364,10 @@ pub mod tests {
     use crate::testing::testing_config::prepare_config;
     use crate::testing::testing_config::TestingConfig;
     use crate::types::config::Config;
+    use crate::utils::{count_in_flight, ConnectionRateLimiter};
     use anyhow::Error;
     use anyhow::Error as AnyhowError;
+    use casper_event_types::metrics::IN_FLIGHT_REQUESTS;
     use pg_embed::pg_enums::PgAuthMethod;
     use pg_embed::postgres::PgSettings;
     use pg_embed::{
@@ -168,6 +380,38 @@ pub mod tests {
     use tokio::sync::mpsc::Receiver;
     use tokio::time::timeout;
 
+    #[tokio::test]
+    async fn should_count_in_flight_requests_until_they_are_dropped() {
+        let gauge = IN_FLIGHT_REQUESTS.with_label_values(&["test"]);
+        let mut request = Box::pin(count_in_flight("test", futures::future::pending::<()>()));
+
+        assert!(futures::poll!(&mut request).is_pending());
+        assert_eq!(gauge.get(), 1);
+        drop(request);
+        assert_eq!(gauge.get(), 0);
+
+        count_in_flight("test", async {}).await;
+        assert_eq!(gauge.get(), 0);
+    }
+
+    #[test]
+    fn connection_rate_limiter_should_allow_bursts_of_one_second() {
+        let mut rate_limiter = ConnectionRateLimiter::new(2);
+        let start = rate_limiter.last_refill;
+
+        assert!(rate_limiter.try_acquire(start));
+        assert!(rate_limiter.try_acquire(start));
+        assert!(!rate_limiter.try_acquire(start));
+
+        assert!(rate_limiter.try_acquire(start + Duration::from_millis(500)));
+        assert!(!rate_limiter.try_acquire(start + Duration::from_millis(600)));
+
+        let later = start + Duration::from_secs(10);
+        assert!(rate_limiter.try_acquire(later));
+        assert!(rate_limiter.try_acquire(later));
+        assert!(!rate_limiter.try_acquire(later));
+    }
+
     pub(crate) fn display_duration(duration: Duration) -> String {
         // less than a second
         if duration.as_millis() < 1000 {
@@ -1,5 +1,8 @@
 use once_cell::sync::Lazy;
-use prometheus::{GaugeVec, HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry};
+use prometheus::{
+    core::Collector, GaugeVec, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts,
+    Registry,
+};
 #[cfg(feature = "additional-metrics")]
 const DB_OPERATION_BUCKETS: &[f64; 8] = &[
     3e+5_f64, 3e+6_f64, 10e+6_f64, 20e+6_f64, 5e+7_f64, 1e+8_f64, 5e+8_f64, 1e+9_f64,
@@ -56,6 +59,59 @@ pub static NODE_STATUSES: Lazy<GaugeVec> = Lazy::new(|| {
         .expect("cannot register metric");
     counter
 });
+pub static NODE_RECONNECTION_ATTEMPTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    let counter = IntCounterVec::new(
+        Opts::new(
+            "node_reconnection_attempts",
+            "Number of attempts the sidecar made to reconnect to a node",
+        ),
+        &["node"],
+    )
+    .expect("metric can't be created");
+    REGISTRY
+        .register(Box::new(counter.clone()))
+        .expect("cannot register metric");
+    counter
+});
+pub static SSE_SUBSCRIBERS: Lazy<IntGaugeVec> = Lazy::new(|| {
+    let counter = IntGaugeVec::new(
+        Opts::new("sse_subscribers", "Subscribers of the sidecar's event stream server. Split by \"type\": \"current\" is the number of connected subscribers, \"max\" is the configured limit (max_concurrent_subscribers)."),
+        &["type"]
+    )
+    .expect("metric can't be created");
+    REGISTRY
+        .register(Box::new(counter.clone()))
+        .expect("cannot register metric");
+    counter
+});
+pub static INCOMING_CONNECTIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    let counter = IntCounterVec::new(
+        Opts::new(
+            "incoming_connections",
+            "Connections made to the sidecar's servers. Split by \"server\" and by \"outcome\": \"accepted\", or \"rejected\" if the connection exceeded max_connections_per_second.",
+        ),
+        &["server", "outcome"],
+    )
+    .expect("metric can't be created");
+    REGISTRY
+        .register(Box::new(counter.clone()))
+        .expect("cannot register metric");
+    counter
+});
+pub static IN_FLIGHT_REQUESTS: Lazy<IntGaugeVec> = Lazy::new(|| {
+    let counter = IntGaugeVec::new(
+        Opts::new(
+            "in_flight_requests",
+            "Requests the sidecar's REST and admin servers are currently handling, split by \"server\". Requests waiting for a slot under max_concurrent_requests aren't counted.",
+        ),
+        &["server"],
+    )
+    .expect("metric can't be created");
+    REGISTRY
+        .register(Box::new(counter.clone()))
+        .expect("cannot register metric");
+    counter
+});
 
 #[cfg(feature = "additional-metrics")]
 pub static DB_OPERATION_TIMES: Lazy<HistogramVec> = Lazy::new(|| {
@@ -88,6 +144,19 @@ pub static EVENTS_PROCESSED_PER_SECOND: Lazy<GaugeVec> = Lazy::new(|| {
     counter
 });
 
+/// Value of `NODE_STATUSES` for a node the sidecar is connected to.
+pub const NODE_STATUS_CONNECTED: f64 = 2.0;
+
+/// Returns the number of nodes which `NODE_STATUSES` currently reports as connected.
+pub fn connected_nodes_count() -> usize {
+    NODE_STATUSES
+        .collect()
+        .iter()
+        .flat_map(|family| family.get_metric())
+        .filter(|metric| metric.get_gauge().get_value() == NODE_STATUS_CONNECTED)
+        .count()
+}
+
 pub struct MetricCollectionError {
     reason: String,
 }